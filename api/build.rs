@@ -0,0 +1,4 @@
+fn main() {
+    // Migrations get embedded into the binary, so changes to them need a rebuild
+    println!("cargo:rerun-if-changed=migrations");
+}
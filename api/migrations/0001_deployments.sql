@@ -0,0 +1,23 @@
+CREATE TABLE IF NOT EXISTS deployments (
+    id TEXT PRIMARY KEY,
+    project TEXT NOT NULL,
+    state TEXT NOT NULL,
+    host TEXT NOT NULL,
+    build_logs TEXT,
+    database_deployment TEXT,
+    so_path TEXT,
+    created_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS states (
+    deployment_id TEXT NOT NULL,
+    state TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS logs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    deployment_id TEXT NOT NULL,
+    datetime TEXT NOT NULL,
+    item TEXT NOT NULL
+);
@@ -0,0 +1,2 @@
+-- Deployments from before had their logs filtered at the default level
+ALTER TABLE deployments ADD COLUMN log_level TEXT NOT NULL DEFAULT 'INFO';
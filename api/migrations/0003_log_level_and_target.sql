@@ -0,0 +1,16 @@
+-- Pulled out of the stored items, so logs can be filtered on them
+ALTER TABLE logs ADD COLUMN level INTEGER NOT NULL DEFAULT 3;
+ALTER TABLE logs ADD COLUMN target TEXT NOT NULL DEFAULT '';
+
+UPDATE logs SET
+    level = CASE json_extract(item, '$.level')
+        WHEN 'ERROR' THEN 1
+        WHEN 'WARN' THEN 2
+        WHEN 'INFO' THEN 3
+        WHEN 'DEBUG' THEN 4
+        WHEN 'TRACE' THEN 5
+        ELSE 3
+    END,
+    target = COALESCE(json_extract(item, '$.target'), '');
+
+CREATE INDEX IF NOT EXISTS logs_deployment_id ON logs (deployment_id, id);
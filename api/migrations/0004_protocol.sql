@@ -0,0 +1,3 @@
+-- Every deployment from before took HTTP requests
+ALTER TABLE deployments ADD COLUMN protocol TEXT NOT NULL DEFAULT 'http';
+ALTER TABLE deployments ADD COLUMN tcp_port INTEGER;
@@ -0,0 +1 @@
+ALTER TABLE deployments ADD COLUMN cron_jobs TEXT;
@@ -0,0 +1,2 @@
+ALTER TABLE deployments ADD COLUMN restarts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE deployments ADD COLUMN last_crash TEXT;
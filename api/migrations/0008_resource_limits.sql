@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS resource_limits (
+    project TEXT PRIMARY KEY,
+    limits TEXT NOT NULL
+);
@@ -0,0 +1,2 @@
+-- Every kind of resource provisioned for a deployment, not only its database
+ALTER TABLE deployments ADD COLUMN resources TEXT;
@@ -0,0 +1,35 @@
+-- The database of a deployment used to be kept on its own, and is now one of its resources.
+-- Shared databases are the ones named after their project, AWS RDS ones after their engine.
+UPDATE deployments SET resources = json_array(json_object(
+    'type', json(CASE
+        WHEN json_extract(database_deployment, '$.engine') = 'postgres'
+            AND json_extract(database_deployment, '$.database_name') LIKE 'db-%'
+            THEN '{"Database":"Shared"}'
+        WHEN json_extract(database_deployment, '$.engine') = 'postgres'
+            THEN '{"Database":{"AwsRds":"Postgres"}}'
+        WHEN json_extract(database_deployment, '$.engine') = 'mysql'
+            THEN '{"Database":{"AwsRds":"MySql"}}'
+        ELSE '{"Database":{"AwsRds":"MariaDB"}}'
+    END),
+    'connection_string_private', printf(
+        '%s://%s:%s@%s:%s/%s',
+        json_extract(database_deployment, '$.engine'),
+        json_extract(database_deployment, '$.role_name'),
+        json_extract(database_deployment, '$.role_password'),
+        json_extract(database_deployment, '$.address_private'),
+        json_extract(database_deployment, '$.port'),
+        json_extract(database_deployment, '$.database_name')
+    ),
+    'connection_string_public', printf(
+        '%s://%s:%s@%s:%s/%s',
+        json_extract(database_deployment, '$.engine'),
+        json_extract(database_deployment, '$.role_name'),
+        json_extract(database_deployment, '$.role_password'),
+        json_extract(database_deployment, '$.address_public'),
+        json_extract(database_deployment, '$.port'),
+        json_extract(database_deployment, '$.database_name')
+    )
+))
+WHERE database_deployment IS NOT NULL AND resources IS NULL;
+
+ALTER TABLE deployments DROP COLUMN database_deployment;
@@ -1,4 +1,5 @@
 use std::net::IpAddr;
+use std::ops::RangeInclusive;
 use std::path::PathBuf;
 use std::str::FromStr;
 
@@ -12,12 +13,54 @@ pub struct Args {
     /// Override the default root path for shuttle
     #[clap(long)]
     pub(crate) path: Option<PathBuf>,
+    /// Override the default location of the deployments state database
+    #[clap(long)]
+    pub(crate) state: Option<PathBuf>,
+    /// Number of latest runtime logs kept for every deployment
+    #[clap(long, default_value = "10000")]
+    pub(crate) max_logs_per_deployment: u32,
+    /// Drop runtime logs once they are older than this many hours
+    #[clap(long)]
+    pub(crate) log_retention_hours: Option<u32>,
+    /// Seconds a new service gets to be ready to take requests before its
+    /// deployment fails
+    #[clap(long, default_value = "60")]
+    pub(crate) readiness_timeout_secs: u64,
+    /// Seconds a stopped service gets to finish the requests it is handling
+    /// before it is aborted
+    #[clap(long, default_value = "30")]
+    pub(crate) drain_timeout_secs: u64,
     /// Override the default port for the proxy
     #[clap(long, default_value = "8000")]
     pub(crate) proxy_port: Port,
+    /// Range of public ports handed out to services taking raw TCP
+    /// connections, as in `9000-9999`. TCP services are refused without it
+    #[clap(long, parse(try_from_str = parse_port_range))]
+    pub(crate) tcp_ports: Option<RangeInclusive<Port>>,
     /// Override the default port for the api
     #[clap(long, default_value = "8001")]
     pub(crate) api_port: Port,
+    /// Override the default port on localhost for runner processes to reach
+    /// the api
+    #[clap(long, default_value = "8002")]
+    pub(crate) supervisor_port: Port,
+    /// Runner binary every deployment gets run in a process of
+    #[clap(long, default_value = "shuttle-runner")]
+    pub(crate) runner_path: PathBuf,
+    /// Cgroup v2 directory delegated to the api, with the `cpu` controller
+    /// enabled for its children. Every runner gets a cgroup of its own in it
+    /// which its cpu limit is enforced with. Cpu limits are refused without
+    /// it, and it needs runners to run as another user
+    #[clap(long)]
+    pub(crate) runner_cgroup: Option<PathBuf>,
+    /// User to run runners as, which takes the api to be privileged enough
+    /// to switch users. Keeps services away from the files of the api and
+    /// the cgroups holding them to their cpu limits
+    #[clap(long)]
+    pub(crate) runner_uid: Option<u32>,
+    /// Group to run runners as
+    #[clap(long)]
+    pub(crate) runner_gid: Option<u32>,
     /// Override the default bind address
     #[clap(long, default_value = "127.0.0.1")]
     pub(crate) bind_addr: IpAddr,
@@ -35,3 +78,17 @@ pub struct Args {
 fn parse_fqdn(src: &str) -> Result<FQDN, String> {
     FQDN::from_str(src).map_err(|e| format!("{e:?}"))
 }
+
+fn parse_port_range(src: &str) -> Result<RangeInclusive<Port>, String> {
+    let (start, end) = src
+        .split_once('-')
+        .ok_or_else(|| "expected a range of ports like `9000-9999`".to_string())?;
+    let start: Port = start.parse().map_err(|e| format!("{e}"))?;
+    let end: Port = end.parse().map_err(|e| format!("{e}"))?;
+
+    if start > end {
+        return Err("the range of ports is empty".to_string());
+    }
+
+    Ok(start..=end)
+}
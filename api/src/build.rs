@@ -19,6 +19,20 @@ pub(crate) struct Build {
     pub(crate) so_path: PathBuf,
 }
 
+impl Build {
+    /// Reuses the shared object of an earlier build, for example to roll back
+    /// to it. The file gets copied under a new name since the old one may
+    /// still be held by `libloading`.
+    pub(crate) fn reuse(so_path: &Path) -> Result<Self> {
+        let project_path = so_path
+            .parent()
+            .ok_or_else(|| anyhow!("shared object {:?} has no parent directory", so_path))?;
+        let so_path = create_unique_named_so_file(project_path, so_path)?;
+
+        Ok(Self { so_path })
+    }
+}
+
 // remove the trait at some point
 #[async_trait]
 pub(crate) trait BuildSystem: Send + Sync {
@@ -103,9 +117,6 @@ impl BuildSystem for FsBuildSystem {
         // create uniquely named so file to satisfy `libloading`
         let so_path = create_unique_named_so_file(&project_path, &so_path)?;
 
-        // create marker file
-        create_so_marker(&project_path, &so_path)?;
-
         Ok(Build { so_path })
     }
 
@@ -114,15 +125,6 @@ impl BuildSystem for FsBuildSystem {
     }
 }
 
-/// Creates a marker file with the location of the `so` file
-/// so that we can use it when bootstrapping the deployment
-/// system
-fn create_so_marker(project_path: &Path, so_path: &Path) -> Result<()> {
-    let marker_path = project_path.join(".shuttle_marker");
-    // unwraps here are ok since we are writing a valid `Path`
-    Ok(std::fs::write(&marker_path, so_path.to_str().unwrap())?)
-}
-
 /// Copies the original `so` file to the project directory with a random name
 /// to appease `libloading`.
 fn create_unique_named_so_file(project_path: &Path, so_path: &Path) -> Result<PathBuf> {
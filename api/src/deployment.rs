@@ -1,41 +1,84 @@
 use core::default::Default;
 use std::collections::HashMap;
-use std::fs::DirEntry;
 use std::io::Write;
 use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, TcpListener};
-use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use anyhow::{anyhow, Context as AnyhowContext};
-use chrono::{DateTime, Utc};
+use futures::channel::oneshot;
 use futures::prelude::*;
-use libloading::Library;
+use hyper::{Client, Uri};
+use log::LevelFilter;
 use rocket::data::ByteUnit;
 use rocket::{tokio, Data};
 use shuttle_common::project::ProjectName;
+use shuttle_common::resource;
 use shuttle_common::{
-    DeploymentApiError, DeploymentId, DeploymentMeta, DeploymentStateMeta, Host, LogItem, Port,
+    BuildEvent, CronJobMeta, DeploymentApiError, DeploymentId, DeploymentMeta, DeploymentStateMeta,
+    DeploymentSummary, Host, LogFilter, LogItem, Port, Protocol, ResourceLimits, RuntimeLog,
+    CRON_LOG_TARGET,
 };
 use shuttle_proto::provisioner::provisioner_client::ProvisionerClient;
-use shuttle_service::loader::Loader;
+use shuttle_proto::runtime::supervisor_server::SupervisorServer;
 use shuttle_service::logger::Log;
-use shuttle_service::ServeHandle;
 use tokio::sync::mpsc::UnboundedSender;
-use tokio::sync::{mpsc, RwLock};
-use tonic::transport::{Channel, Endpoint};
+use tokio::sync::{broadcast, mpsc, RwLock};
+use tonic::transport::{Endpoint, Server};
 
 use crate::build::Build;
+use crate::persistence::{LogRetention, Persistence};
 use crate::router::Router;
-use crate::{BuildSystem, ShuttleFactory};
-
-// This controls the maximum number of deploys an api instance can run
-// This is mainly needed because tokio::task::spawn_blocking keeps an internal pool for the number of blocking threads
-// and we call this method to run each incoming service. Therefore, this variable directly maps to this maximum pool
-// when the runtime is setup in main()
-// The current tokio default for this pool is 512
-// https://docs.rs/tokio/latest/tokio/runtime/struct.Builder.html#method.max_blocking_threads
+use crate::runner::{Runner, RunnerConfig, RunnerSupervisor, Secrets, Started, Starts};
+use crate::tcp::TcpProxy;
+use crate::BuildSystem;
+
+// This controls the maximum number of deploys an api instance can run, each in
+// a runner process of its own
 pub const MAX_DEPLOYS: usize = 512;
 
+// Number of runtime logs kept for clients following the logs of a deployment.
+// Clients that fall further behind than this will skip the logs they missed.
+const LOG_STREAM_SIZE: usize = 1024;
+
+// Number of build events kept for clients following the build of a deployment.
+const BUILD_STREAM_SIZE: usize = 1024;
+
+// How often runtime logs outside of the retention get dropped.
+const LOG_PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+// How often a new deployment gets checked for being ready to take requests.
+const READINESS_INTERVAL: Duration = Duration::from_millis(250);
+
+// How often deployed services get checked on, and how long a single health
+// probe of a service may take.
+const HEALTH_INTERVAL: Duration = Duration::from_secs(10);
+const HEALTH_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Number of health probes in a row a service has to fail to count as crashed,
+// so that a single slow answer does not get it restarted.
+const HEALTH_PROBE_FAILURES: u32 = 3;
+
+// How long to wait before restarting a crashed service. The wait doubles with
+// every crash that follows, up to the maximum, until the service manages to
+// keep running for longer than that maximum.
+const RESTART_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(300);
+
+/// A stored runtime log together with the project of the deployment it
+/// belongs to.
+#[derive(Clone, Debug)]
+pub(crate) struct DeploymentLog {
+    pub(crate) project: ProjectName,
+    pub(crate) log: RuntimeLog,
+}
+
+/// A build event together with the deployment it belongs to.
+#[derive(Clone, Debug)]
+pub(crate) struct BuildLog {
+    pub(crate) deployment_id: DeploymentId,
+    pub(crate) event: BuildEvent,
+}
+
 /// Inner struct of a deployment which holds the deployment itself
 /// and the some metadata
 pub(crate) struct Deployment {
@@ -51,35 +94,23 @@ impl Deployment {
         }
     }
 
-    fn from_bytes(fqdn: &str, project: ProjectName, crate_bytes: Vec<u8>) -> Self {
-        Self {
-            meta: Arc::new(RwLock::new(DeploymentMeta::queued(fqdn, project))),
-            state: RwLock::new(DeploymentState::queued(crate_bytes)),
+    fn from_bytes(
+        fqdn: &str,
+        project: ProjectName,
+        crate_bytes: Vec<u8>,
+        log_level: LevelFilter,
+        tcp_port: Option<Port>,
+        health_path: Option<String>,
+    ) -> Self {
+        let mut meta = DeploymentMeta::queued(fqdn, project);
+        meta.log_level = log_level;
+        meta.health_path = health_path;
+        if tcp_port.is_some() {
+            meta.protocol = Protocol::Tcp;
+            meta.tcp_port = tcp_port;
         }
-    }
 
-    /// Initialise a deployment from a directory
-    fn from_directory(fqdn: &str, dir: DirEntry) -> Result<Self, anyhow::Error> {
-        let project_path = dir.path();
-        let project_name = dir
-            .file_name()
-            .into_string()
-            .map_err(|err| anyhow!("invalid project name: {:?}", err))
-            .and_then(|name| name.parse::<ProjectName>().map_err(|err| err.into()))?;
-        // find marker which points to so file
-        let marker_path = project_path.join(".shuttle_marker");
-        let so_path_str = std::fs::read(&marker_path).context(anyhow!(
-            "could not find so marker file at {:?}",
-            marker_path
-        ))?;
-
-        let so_path: PathBuf = String::from_utf8_lossy(&so_path_str)
-            .parse()
-            .context("could not parse contents of marker file to a valid path")?;
-
-        let meta = DeploymentMeta::built(fqdn, project_name);
-        let state = DeploymentState::built(Build { so_path });
-        Ok(Self::new(meta, state))
+        Self::new(meta, DeploymentState::queued(crate_bytes))
     }
 
     /// Gets a `clone`ed copy of the metadata.
@@ -99,25 +130,28 @@ impl Deployment {
             DeploymentState::Queued(_) | DeploymentState::Built(_) | DeploymentState::Loaded(_) => {
                 false
             }
-            DeploymentState::Deployed(_) | DeploymentState::Error(_) | DeploymentState::Deleted => {
-                true
-            }
+            DeploymentState::Deployed(_)
+            | DeploymentState::Crashed(_)
+            | DeploymentState::Error(_)
+            | DeploymentState::Deleted => true,
         }
     }
 
     /// Tries to advance the deployment one stage. Does nothing if the deployment
     /// is in a terminal state.
-    pub(crate) async fn advance(&self, context: &Context, run_logs_tx: UnboundedSender<Log>) {
-        {
+    pub(crate) async fn advance(&self, context: &Context) {
+        let previous = {
             trace!("waiting to get write on the state");
             let meta = self.meta().await;
             let mut state = self.state.write().await;
+            let previous = state.meta();
 
             *state = match state.take() {
                 DeploymentState::Queued(queued) => {
                     debug!("deployment '{}' build starting...", &meta.id);
 
-                    let console_writer = BuildOutputWriter::new(self.meta.clone());
+                    let console_writer =
+                        BuildOutputWriter::new(meta.id, context.build_logs_tx.clone());
                     match context
                         .build_system
                         .build(
@@ -127,7 +161,17 @@ impl Deployment {
                         )
                         .await
                     {
-                        Ok(build) => DeploymentState::built(build),
+                        Ok(build) => {
+                            if let Err(e) = context
+                                .persistence
+                                .set_so_path(&meta.id, &build.so_path)
+                                .await
+                            {
+                                error!("failed to persist so path of '{}': {}", meta.id, e);
+                            }
+
+                            DeploymentState::built(build)
+                        }
                         Err(e) => {
                             dbg!("failed to build with error: {}", &e);
                             DeploymentState::Error(e)
@@ -135,22 +179,61 @@ impl Deployment {
                     }
                 }
                 DeploymentState::Built(built) => {
-                    debug!(
-                        "deployment '{}' loading shared object and service...",
-                        &meta.id
-                    );
+                    debug!("deployment '{}' starting its runner...", &meta.id);
+
+                    // Services taking raw TCP connections need their public port
+                    // before they can be deployed
+                    let listening = match meta.tcp_port {
+                        Some(tcp_port) => context.tcp_proxy.listen(tcp_port).await,
+                        None => Ok(()),
+                    };
 
-                    match Loader::from_so_file(&built.build.so_path) {
-                        Ok(loader) => DeploymentState::loaded(loader),
+                    match listening {
                         Err(e) => {
-                            debug!("failed to load with error: {}", &e);
+                            debug!("failed to listen for TCP connections: {}", &e);
                             DeploymentState::Error(e.into())
                         }
+                        Ok(()) => {
+                            match context.persistence.get_resource_limits(&meta.project).await {
+                                Err(e) => {
+                                    debug!("failed to get resource limits: {}", &e);
+                                    DeploymentState::Error(e.into())
+                                }
+                                Ok(limits) => {
+                                    // Changed limits apply from the next time the service starts
+                                    self.meta.write().await.limits = limits;
+
+                                    let port = identify_free_port();
+                                    let started = context.starts.expect(meta.id).await;
+
+                                    match Runner::spawn(
+                                        &context.runners,
+                                        &built.build.so_path,
+                                        &meta,
+                                        limits,
+                                        port,
+                                        &context.secrets,
+                                    ) {
+                                        Ok(runner) => DeploymentState::loaded(LoadedState {
+                                            runner,
+                                            port,
+                                            started,
+                                        }),
+                                        Err(e) => {
+                                            debug!("failed to start runner with error: {}", &e);
+                                            DeploymentState::Error(e)
+                                        }
+                                    }
+                                }
+                            }
+                        }
                     }
                 }
-                DeploymentState::Loaded(loader) => {
-                    let port = identify_free_port();
-
+                DeploymentState::Loaded(LoadedState {
+                    mut runner,
+                    port,
+                    started,
+                }) => {
                     debug!(
                         "deployment '{}' getting deployed on port {}...",
                         meta.id, port
@@ -158,115 +241,384 @@ impl Deployment {
 
                     debug!("{}: factory phase", meta.project);
 
-                    let mut factory = ShuttleFactory::new(
-                        context.provisioner_client.clone(),
-                        meta.project.clone(),
-                    );
-                    let addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), port);
-                    match loader.load(&mut factory, addr, run_logs_tx, meta.id).await {
+                    match runner.started(started, context.timeouts.readiness).await {
                         Err(e) => {
-                            debug!("{}: factory phase FAILED: {:?}", meta.project, e);
-                            DeploymentState::Error(e.into())
+                            debug!("{}: factory phase FAILED: {:#}", meta.project, e);
+
+                            if let Err(e) = runner.stop(Duration::ZERO).await {
+                                debug!("runner which failed to start failed: {}", e);
+                            }
+
+                            DeploymentState::Error(e)
                         }
-                        Ok((handle, so)) => {
+                        Ok(routable) => {
                             debug!("{}: factory phase DONE", meta.project);
-                            self.meta.write().await.database_deployment =
-                                factory.into_database_info();
 
-                            // Remove stale active deployments
-                            if let Some(stale_id) = context.router.promote(meta.host, meta.id).await
-                            {
-                                debug!("removing stale deployment `{}`", &stale_id);
-                                context.deployments.write().await.remove(&stale_id);
+                            // Background workers never bind to their port, so nothing
+                            // gets routed to them
+                            let port = if routable { Some(port) } else { None };
+                            let deployed = DeployedState { runner, port };
+
+                            // The deployment this one replaces keeps taking requests
+                            // until this one is ready for them
+                            let ready = match port {
+                                Some(port) => {
+                                    wait_until_ready(
+                                        port,
+                                        meta.health_path.as_deref(),
+                                        context.timeouts.readiness,
+                                    )
+                                    .await
+                                }
+                                None => Ok(()),
+                            };
+
+                            match ready {
+                                Err(e) => {
+                                    debug!("{}: readiness check FAILED: {}", meta.project, e);
+
+                                    // Nothing was ever routed to it, so there is nothing to drain
+                                    if let Err(e) = deployed.stop(Duration::ZERO).await {
+                                        debug!("unready deployment failed: {}", e);
+                                    }
+
+                                    DeploymentState::Error(e)
+                                }
+                                Ok(()) => {
+                                    if let (Some(port), Some(tcp_port)) = (port, meta.tcp_port) {
+                                        context.tcp_proxy.forward(tcp_port, port).await;
+                                    }
+
+                                    // Remove stale active deployments
+                                    let stale_id = context
+                                        .router
+                                        .promote(meta.host, meta.id)
+                                        .await
+                                        // A restarted deployment replaces itself
+                                        .filter(|stale_id| *stale_id != meta.id);
+
+                                    if let Some(stale_id) = stale_id {
+                                        debug!("removing stale deployment `{}`", &stale_id);
+                                        let stale =
+                                            context.deployments.write().await.remove(&stale_id);
+
+                                        if let Some(stale) = stale {
+                                            let drain_timeout = context.timeouts.drain;
+
+                                            tokio::spawn(async move {
+                                                if let Err(e) = stale.stop(drain_timeout).await {
+                                                    warn!(
+                                                        "stale deployment `{}` failed: {}",
+                                                        stale_id, e
+                                                    );
+                                                }
+                                            });
+                                        }
+                                    }
+
+                                    DeploymentState::Deployed(deployed)
+                                }
                             }
-
-                            DeploymentState::deployed(so, port, handle)
                         }
                     }
                 }
                 deployed_or_error => deployed_or_error, /* nothing to do here */
             };
-        }
+
+            previous
+        };
 
         // ensures that the metadata state is inline with the actual state. This
         // can go when we have an API layer.
-        self.update_meta_state().await;
+        self.update_meta_state(previous, context).await;
     }
 
-    async fn update_meta_state(&self) {
-        self.meta.write().await.state = self.state.read().await.meta()
+    /// Records the transition from the `previous` state to the actual state
+    /// if there was one. The transition goes through the build stream, behind
+    /// any build output still on its way, which also updates the metadata.
+    async fn update_meta_state(&self, previous: DeploymentStateMeta, context: &Context) {
+        let state = self.state.read().await.meta();
+
+        if previous != state {
+            let id = self.meta.read().await.id;
+
+            if let Err(e) = context.persistence.update_state(&id, &state).await {
+                error!("failed to persist state of '{}': {}", id, e);
+            }
+
+            // Only fails when the api is shutting down
+            let _ = context.build_logs_tx.send(BuildLog {
+                deployment_id: id,
+                event: BuildEvent::State(state),
+            });
+        }
     }
 
-    async fn port(&self) -> Option<Port> {
-        match &*self.state.read().await {
-            DeploymentState::Deployed(deployed) => Some(deployed.port),
+    /// Records a build event in the metadata and passes it on to the clients
+    /// following the build. Both happen under the same lock so that a client
+    /// subscribing in between sees every event exactly once.
+    async fn add_build_event(&self, log: BuildLog, build_stream: &broadcast::Sender<BuildLog>) {
+        let mut meta = self.meta.write().await;
+
+        match &log.event {
+            BuildEvent::Line(line) => {
+                let build_logs = meta.build_logs.get_or_insert_with(String::new);
+                build_logs.push_str(line);
+                build_logs.push('\n');
+            }
+            BuildEvent::State(state) => meta.state = state.clone(),
+        }
+
+        // Only fails when nobody is following the build right now
+        let _ = build_stream.send(log);
+    }
+
+    /// Records what a service reported about a run of one of its cron jobs
+    /// in the metadata, and stores it.
+    async fn record_cron_run(&self, item: &LogItem, persistence: &Persistence) {
+        let job = match CronJobMeta::from_fields(&item.fields) {
+            Some(job) => job,
+            None => return,
+        };
+
+        let mut meta = self.meta.write().await;
+
+        match meta
+            .cron_jobs
+            .iter_mut()
+            .find(|known| known.name == job.name)
+        {
+            Some(known) => *known = job,
+            None => meta.cron_jobs.push(job),
+        }
+
+        if let Err(e) = persistence.set_cron_jobs(&meta.id, &meta.cron_jobs).await {
+            warn!("failed to persist cron jobs of '{}': {}", meta.id, e);
+        }
+    }
+
+    /// Records a resource provisioned for the service in the metadata, in
+    /// place of any earlier one of the same type and name, and stores them.
+    pub(crate) async fn add_resource(
+        &self,
+        resource: resource::Response,
+        persistence: &Persistence,
+    ) {
+        let mut meta = self.meta.write().await;
+
+        let request = resource.request();
+        meta.resources.retain(|r| !r.is_for(&request));
+        meta.resources.push(resource);
+
+        if let Err(e) = persistence.set_resources(&meta.id, &meta.resources).await {
+            error!("failed to persist resources of '{}': {}", meta.id, e);
+        }
+    }
+
+    /// Records the resources the service is using right now in the metadata.
+    /// The last usage recorded is kept once the service stops.
+    async fn record_usage(&self) {
+        let usage = match &*self.state.read().await {
+            DeploymentState::Deployed(deployed) => deployed.runner.usage().ok(),
             _ => None,
+        };
+
+        if let Some(usage) = usage {
+            self.meta.write().await.usage = Some(usage);
+        }
+    }
+
+    /// Checks whether the service is still running and, if it takes requests,
+    /// whether it still answers.
+    async fn check_health(&self) -> Health {
+        let port = {
+            let mut state = self.state.write().await;
+            let deployed = match &mut *state {
+                DeploymentState::Deployed(deployed) => deployed,
+                _ => return Health::Gone,
+            };
+
+            match deployed.runner.exit_status() {
+                Ok(None) => deployed.port,
+                // Background workers may finish their work
+                Ok(Some(status)) if status.success() && deployed.port.is_none() => {
+                    return Health::Gone;
+                }
+                Ok(Some(status)) if status.success() => {
+                    return Health::Crashed(anyhow::anyhow!("service stopped taking requests"));
+                }
+                Ok(Some(status)) => {
+                    return Health::Crashed(anyhow::anyhow!(
+                        deployed.runner.describe_exit(status).await
+                    ));
+                }
+                Err(e) => return Health::Crashed(e.into()),
+            }
+        };
+
+        let port = match port {
+            Some(port) => port,
+            None => return Health::Healthy,
+        };
+        let health_path = self.meta.read().await.health_path.clone();
+
+        match probe(port, health_path.as_deref(), HEALTH_PROBE_TIMEOUT).await {
+            Ok(()) => Health::Healthy,
+            Err(e) => Health::Unresponsive(e),
+        }
+    }
+
+    /// Stops what is left of a service which crashed and records the crash.
+    async fn crash(&self, error: anyhow::Error, context: &Context) {
+        let message = format!("{:#}", error);
+
+        let previous = {
+            let mut state = self.state.write().await;
+            let previous = state.meta();
+
+            if let DeploymentState::Deployed(deployed) = state.take() {
+                // Whatever it is doing, it is not taking requests
+                if let Err(e) = deployed.stop(Duration::ZERO).await {
+                    debug!("crashed service failed to stop: {}", e);
+                }
+            }
+
+            *state = DeploymentState::Crashed(message.clone());
+
+            previous
+        };
+
+        let mut meta = self.meta.write().await;
+        meta.last_crash = Some(message);
+
+        if let Err(e) = context
+            .persistence
+            .set_crashes(&meta.id, meta.restarts, meta.last_crash.as_deref())
+            .await
+        {
+            error!("failed to persist crash of '{}': {}", meta.id, e);
         }
+        drop(meta);
+
+        self.update_meta_state(previous, context).await;
     }
 
-    async fn add_runtime_log(&self, datetime: DateTime<Utc>, log: LogItem) {
-        self.meta.write().await.runtime_logs.insert(datetime, log);
+    /// Deploys a crashed service again from the shared object it was built
+    /// into. Returns why it failed if it did not get deployed.
+    async fn restart(&self, context: &Context) -> anyhow::Result<()> {
+        let id = self.meta.read().await.id;
+        let so_path = context
+            .persistence
+            .get_so_path(&id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("the build of the service is gone"))?;
+        let build = Build::reuse(&so_path)?;
+
+        let previous = {
+            let mut state = self.state.write().await;
+            let previous = state.meta();
+
+            // Stopped or replaced while waiting to be restarted
+            if !matches!(*state, DeploymentState::Crashed(_)) {
+                return Ok(());
+            }
+
+            *state = DeploymentState::built(build);
+
+            previous
+        };
+
+        {
+            let mut meta = self.meta.write().await;
+            meta.restarts += 1;
+
+            if let Err(e) = context
+                .persistence
+                .set_crashes(&meta.id, meta.restarts, meta.last_crash.as_deref())
+                .await
+            {
+                error!("failed to persist restart of '{}': {}", meta.id, e);
+            }
+        }
+        self.update_meta_state(previous, context).await;
+
+        while !self.deployment_finished().await {
+            self.advance(context).await;
+        }
+
+        match &*self.state.read().await {
+            DeploymentState::Error(e) => Err(anyhow::anyhow!("{:#}", e)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Shuts the service down if it is deployed, see [`DeployedState::stop`].
+    async fn stop(&self, drain_timeout: Duration) -> Result<(), DeploymentApiError> {
+        let mut state = self.state.write().await;
+
+        match state.take() {
+            DeploymentState::Deployed(deployed) => deployed.stop(drain_timeout).await,
+            _ => Ok(()),
+        }
+    }
+
+    async fn port(&self) -> Option<Port> {
+        match &*self.state.read().await {
+            DeploymentState::Deployed(deployed) => deployed.port,
+            _ => None,
+        }
     }
 }
 
 /// Provides a `Write` wrapper around the build logs - i.e., the build output
-/// is written into our build logs using this wrapper.
+/// is sent line by line to the build stream using this wrapper.
 struct BuildOutputWriter {
-    meta: Arc<RwLock<DeploymentMeta>>,
+    id: DeploymentId,
+    build_logs_tx: UnboundedSender<BuildLog>,
     buf: String,
 }
 
 impl BuildOutputWriter {
-    pub fn new(meta: Arc<RwLock<DeploymentMeta>>) -> Self {
+    pub fn new(id: DeploymentId, build_logs_tx: UnboundedSender<BuildLog>) -> Self {
         Self {
-            meta,
+            id,
+            build_logs_tx,
             buf: String::new(),
         }
     }
+
+    fn send_line(&self, line: String) {
+        // Only fails when the api is shutting down
+        let _ = self.build_logs_tx.send(BuildLog {
+            deployment_id: self.id,
+            event: BuildEvent::Line(line),
+        });
+    }
 }
 
 impl Write for BuildOutputWriter {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        let write_len = buf.len();
-        let is_new_line = buf[write_len - 1] == b'\n';
         if let Ok(buf) = std::str::from_utf8(buf) {
             self.buf.push_str(buf);
 
-            // The flush step introduces async code which can potentially execute out of order.
-            // For example, if the write is called with inputs a, b, and c then a new threads will be
-            // spawned to add a, b, and c to meta. However, the threads might execute in others b, a,
-            // and c if they are too close to one another. This `write` method seems to be called for
-            // every character which causes many threads with overlapping lifetimes and therefore
-            // many out of order executions which just mess up the log output.
-            // Since line orders rarely matter and only spawning a thread for each output line also
-            // reduces the likelihood of threads with overlapping lifetimes, the guard exists.
-            if is_new_line {
-                // Safe to unwrap since `flush` has no errors internally
-                self.flush().unwrap();
+            // Sending never blocks and all lines go through the same channel,
+            // so they arrive in the order they were written
+            while let Some(end) = self.buf.find('\n') {
+                let line: String = self.buf.drain(..=end).collect();
+                self.send_line(line.trim_end_matches(&['\r', '\n'][..]).to_string());
             }
 
-            return Ok(write_len);
+            return Ok(buf.len());
         }
 
         Ok(0)
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
-        let handle = tokio::runtime::Handle::current();
-        let meta = self.meta.clone();
-        let buf = self.buf.clone();
-        self.buf = String::new();
-
-        // Can't call `block_on` on a thread that already has a tokio executor, so spawn a new one
-        std::thread::spawn(move || {
-            handle.block_on(async {
-                meta.write()
-                    .await
-                    .build_logs
-                    .get_or_insert("".to_string())
-                    .push_str(&buf)
-            });
-        });
+        if !self.buf.is_empty() {
+            let line = std::mem::take(&mut self.buf);
+            self.send_line(line);
+        }
 
         Ok(())
     }
@@ -279,7 +631,7 @@ impl Drop for BuildOutputWriter {
     }
 }
 
-type Deployments = HashMap<DeploymentId, Arc<Deployment>>;
+pub(crate) type Deployments = HashMap<DeploymentId, Arc<Deployment>>;
 
 /// The top-level manager for deployments. Is responsible for their creation
 /// and lifecycle.
@@ -287,7 +639,14 @@ pub(crate) struct DeploymentSystem {
     deployments: Arc<RwLock<Deployments>>,
     job_queue: JobQueue,
     router: Arc<Router>,
+    tcp_proxy: Arc<TcpProxy>,
+    persistence: Persistence,
+    log_stream: broadcast::Sender<DeploymentLog>,
+    build_stream: broadcast::Sender<BuildLog>,
     fqdn: String,
+    timeouts: Timeouts,
+    /// Whether runners can be held to cpu limits, see [`RunnerConfig::cgroup`]
+    cpu_limits: bool,
 }
 
 const JOB_QUEUE_SIZE: usize = 200;
@@ -297,8 +656,9 @@ struct JobQueue {
 }
 
 impl JobQueue {
-    async fn new(context: Context, run_logs_tx: UnboundedSender<Log>) -> Self {
+    async fn new(context: Context) -> Self {
         let (send, mut recv) = mpsc::channel::<Arc<Deployment>>(JOB_QUEUE_SIZE);
+        let context = Arc::new(context);
 
         log::debug!("starting job processor task");
 
@@ -309,9 +669,11 @@ impl JobQueue {
                 log::debug!("started deployment job for deployment '{}'", id);
 
                 while !deployment.deployment_finished().await {
-                    let run_logs_tx = run_logs_tx.clone();
+                    deployment.advance(&context).await;
+                }
 
-                    deployment.advance(&context, run_logs_tx).await;
+                if deployment.deployment_active().await {
+                    tokio::spawn(monitor(deployment.clone(), context.clone()));
                 }
 
                 debug!("ended deployment job for id: '{}'", id);
@@ -335,55 +697,185 @@ impl JobQueue {
 /// processor.
 pub(crate) struct Context {
     router: Arc<Router>,
+    tcp_proxy: Arc<TcpProxy>,
     build_system: Box<dyn BuildSystem>,
     deployments: Arc<RwLock<Deployments>>,
-    provisioner_client: ProvisionerClient<Channel>,
+    runners: RunnerConfig,
+    starts: Starts,
+    secrets: Secrets,
+    persistence: Persistence,
+    build_logs_tx: UnboundedSender<BuildLog>,
+    timeouts: Timeouts,
+}
+
+/// How long services get for the steps around them taking requests.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Timeouts {
+    /// To be ready to take requests once they are started
+    pub(crate) readiness: Duration,
+    /// To finish the requests they are handling once they are stopped
+    pub(crate) drain: Duration,
 }
 
 impl DeploymentSystem {
     pub(crate) async fn new(
         build_system: Box<dyn BuildSystem>,
         fqdn: String,
-        provisioner_address: String,
-        provisioner_port: Port,
+        runners: RunnerConfig,
+        persistence: Persistence,
+        log_retention: LogRetention,
+        tcp_proxy: TcpProxy,
+        timeouts: Timeouts,
     ) -> Self {
         let router: Arc<Router> = Default::default();
+        let tcp_proxy = Arc::new(tcp_proxy);
         let (tx, mut rx) = mpsc::unbounded_channel::<Log>();
 
-        let deployments = Arc::new(RwLock::new(
-            Self::initialise_from_fs(&build_system.fs_root(), &fqdn).await,
-        ));
+        let deployments = Arc::new(RwLock::new(Self::initialise_from_db(&persistence).await));
 
+        let persistence_log = persistence.clone();
         let deployments_log = deployments.clone();
+        let (log_stream, _) = broadcast::channel(LOG_STREAM_SIZE);
+        let log_stream_send = log_stream.clone();
 
         tokio::spawn(async move {
             while let Some(log) = rx.recv().await {
-                let mut deployments_log = deployments_log.write().await;
+                let id = match persistence_log
+                    .insert_log(&log.deployment_id, log.datetime, &log.item)
+                    .await
+                {
+                    Ok(id) => id,
+                    Err(e) => {
+                        warn!("failed to persist log of '{}': {}", log.deployment_id, e);
+                        continue;
+                    }
+                };
 
-                if let Some(deployment) = deployments_log.get_mut(&log.deployment_id) {
-                    deployment.add_runtime_log(log.datetime, log.item).await;
+                let deployment = deployments_log
+                    .read()
+                    .await
+                    .get(&log.deployment_id)
+                    .cloned();
+                let deployment = match deployment {
+                    Some(deployment) => deployment,
+                    None => continue,
+                };
+
+                if log.item.target == CRON_LOG_TARGET {
+                    deployment
+                        .record_cron_run(&log.item, &persistence_log)
+                        .await;
                 }
+
+                let project = deployment.meta.read().await.project.clone();
+
+                // Only fails when nobody is following the logs right now
+                let _ = log_stream_send.send(DeploymentLog {
+                    project,
+                    log: RuntimeLog {
+                        id,
+                        datetime: log.datetime,
+                        item: log.item,
+                    },
+                });
             }
         });
 
-        let provisioner_uri = Endpoint::try_from(format!(
-            "http://{}:{}",
-            provisioner_address, provisioner_port
-        ))
-        .expect("provisioner uri to be valid");
+        let persistence_prune = persistence.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(LOG_PRUNE_INTERVAL);
+
+            loop {
+                interval.tick().await;
+
+                match persistence_prune.prune_logs(log_retention).await {
+                    Ok(0) => {}
+                    Ok(pruned) => debug!("dropped {} runtime logs outside of retention", pruned),
+                    Err(e) => warn!("failed to prune runtime logs: {}", e),
+                }
+            }
+        });
+
+        let (build_logs_tx, mut build_logs_rx) = mpsc::unbounded_channel::<BuildLog>();
+        let deployments_build = deployments.clone();
+        let persistence_build = persistence.clone();
+        let (build_stream, _) = broadcast::channel(BUILD_STREAM_SIZE);
+        let build_stream_send = build_stream.clone();
+
+        tokio::spawn(async move {
+            while let Some(log) = build_logs_rx.recv().await {
+                if let BuildEvent::Line(line) = &log.event {
+                    if let Err(e) = persistence_build
+                        .append_build_logs(&log.deployment_id, &format!("{}\n", line))
+                        .await
+                    {
+                        warn!(
+                            "failed to persist build logs of '{}': {}",
+                            log.deployment_id, e
+                        );
+                    }
+                }
+
+                let deployment = deployments_build
+                    .read()
+                    .await
+                    .get(&log.deployment_id)
+                    .cloned();
+
+                match deployment {
+                    Some(deployment) => deployment.add_build_event(log, &build_stream_send).await,
+                    None => {
+                        let _ = build_stream_send.send(log);
+                    }
+                }
+            }
+        });
+
+        let provisioner_uri = Endpoint::try_from(runners.provisioner_uri.clone())
+            .expect("provisioner uri to be valid");
 
         let provisioner_client = ProvisionerClient::connect(provisioner_uri)
             .await
             .expect("failed to connect to provisioner");
 
+        let starts = Starts::default();
+        let secrets = Secrets::default();
+        let supervisor = RunnerSupervisor::new(
+            deployments.clone(),
+            persistence.clone(),
+            provisioner_client,
+            tx,
+            starts.clone(),
+            secrets.clone(),
+        );
+        let supervisor_addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), runners.supervisor_port);
+
+        tokio::spawn(async move {
+            if let Err(e) = Server::builder()
+                .add_service(SupervisorServer::new(supervisor))
+                .serve(supervisor_addr)
+                .await
+            {
+                error!("failed to serve runners on {}: {}", supervisor_addr, e);
+            }
+        });
+
+        let cpu_limits = runners.cgroup.is_some();
         let context = Context {
             router: router.clone(),
+            tcp_proxy: tcp_proxy.clone(),
             build_system,
             deployments: deployments.clone(),
-            provisioner_client,
+            runners,
+            starts,
+            secrets,
+            persistence: persistence.clone(),
+            build_logs_tx,
+            timeouts,
         };
 
-        let job_queue = JobQueue::new(context, tx).await;
+        let job_queue = JobQueue::new(context).await;
 
         debug!("loading deployments into job processor");
         for deployment in deployments.read().await.values() {
@@ -395,43 +887,93 @@ impl DeploymentSystem {
             deployments,
             job_queue,
             router,
+            tcp_proxy,
+            persistence,
+            log_stream,
+            build_stream,
             fqdn,
+            timeouts,
+            cpu_limits,
+        }
+    }
+
+    /// Subscribes to the runtime logs of all deployments as they get stored.
+    pub(crate) fn subscribe_logs(&self) -> broadcast::Receiver<DeploymentLog> {
+        self.log_stream.subscribe()
+    }
+
+    /// Retrieves one page of the stored runtime logs of a deployment which
+    /// match `filter`, oldest first. See [`Persistence::get_logs`].
+    pub(crate) async fn get_logs(
+        &self,
+        id: &DeploymentId,
+        filter: &LogFilter,
+        after: Option<i64>,
+        limit: u32,
+    ) -> Result<Vec<RuntimeLog>, DeploymentApiError> {
+        self.persistence
+            .get_logs(id, filter, after, limit)
+            .await
+            .map_err(|e| DeploymentApiError::Internal(e.to_string()))
+    }
+
+    /// Subscribes to the build of a deployment. Returns the metadata with the
+    /// build output so far and, if the deployment is still being tracked, a
+    /// receiver for the build events of all deployments that come after it.
+    pub(crate) async fn subscribe_build(
+        &self,
+        id: &DeploymentId,
+    ) -> Result<(DeploymentMeta, Option<broadcast::Receiver<BuildLog>>), DeploymentApiError> {
+        if let Some(deployment) = self.deployments.read().await.get(id) {
+            // Hold on to the metadata until subscribed so no event is missed
+            let meta = deployment.meta.read().await;
+
+            return Ok((meta.clone(), Some(self.build_stream.subscribe())));
         }
+
+        self.get_deployment(id).await.map(|meta| (meta, None))
     }
 
-    /// Traverse the build directory re-create deployments.
-    /// If a project could not be re-created, this will get logged and skipped.
-    async fn initialise_from_fs(fs_root: &Path, fqdn: &str) -> Deployments {
+    /// Re-create the deployments that were running before the api restarted
+    /// from their stored shared objects. They are loaded again from scratch, so
+    /// they start off as built.
+    async fn initialise_from_db(persistence: &Persistence) -> Deployments {
+        let interrupted = persistence
+            .fail_interrupted_deployments()
+            .await
+            .expect("failed to update deployments in the state database");
+        if interrupted > 0 {
+            warn!(
+                "{} deployment(s) were waiting to be built when the api stopped and failed",
+                interrupted
+            );
+        }
+
+        let restorable = persistence
+            .get_restorable_deployments()
+            .await
+            .expect("failed to read deployments from the state database");
+
         let mut deployments = HashMap::default();
-        for project_dir in std::fs::read_dir(fs_root).unwrap() {
-            let project_dir = match project_dir {
-                Ok(project_dir) => project_dir,
-                Err(e) => {
-                    warn!("failed to read directory for project with error `{:?}`", e);
-                    warn!("skipping...");
-                    continue;
-                }
-            };
-            let project_name = project_dir.file_name();
-            match Deployment::from_directory(fqdn, project_dir) {
-                Err(e) => {
-                    warn!(
-                        "failed to re-create deployment for project `{:?}` with error: {:?}",
-                        project_name, e
-                    );
-                }
-                Ok(deployment) => {
-                    let deployment = Arc::new(deployment);
-                    let info = deployment.meta().await;
-                    deployments.insert(info.id, deployment.clone());
-                }
+        for (mut meta, so_path) in restorable {
+            if !so_path.exists() {
+                warn!(
+                    "shared object `{:?}` for deployment '{}' is gone, skipping...",
+                    so_path, meta.id
+                );
+                continue;
             }
+
+            meta.state = DeploymentStateMeta::Built;
+            let id = meta.id;
+            let deployment = Deployment::new(meta, DeploymentState::built(Build { so_path }));
+            deployments.insert(id, Arc::new(deployment));
         }
         deployments
     }
 
-    /// Returns the port for a given host. If the host does not exist, returns
-    /// `None`.
+    /// Returns the port for a given host. If the host does not exist, or its
+    /// service takes no requests, returns `None`.
     pub(crate) async fn port_for_host(&self, host: &Host) -> Option<Port> {
         let id_for_host = self.router.id_for_host(host).await?;
         self.deployments
@@ -447,13 +989,18 @@ impl DeploymentSystem {
         &self,
         id: &DeploymentId,
     ) -> Result<DeploymentMeta, DeploymentApiError> {
-        match self.deployments.read().await.get(id) {
-            Some(deployment) => Ok(deployment.meta().await),
-            None => Err(DeploymentApiError::NotFound(format!(
-                "could not find deployment for id '{}'",
-                &id
-            ))),
+        if let Some(deployment) = self.deployments.read().await.get(id) {
+            return Ok(deployment.meta().await);
         }
+
+        // Deployments which are no longer running are only kept in the database
+        self.persistence
+            .get_deployment(id)
+            .await
+            .map_err(|e| DeploymentApiError::Internal(e.to_string()))?
+            .ok_or_else(|| {
+                DeploymentApiError::NotFound(format!("could not find deployment for id '{}'", &id))
+            })
     }
 
     /// Retrieves a clone of the deployment information
@@ -484,48 +1031,89 @@ impl DeploymentSystem {
         }
     }
 
+    /// Retrieves one page of the deployment history of a project, with the
+    /// latest deployment first.
+    pub(crate) async fn get_deployments_for_project(
+        &self,
+        project_name: &ProjectName,
+        page: u32,
+        limit: u32,
+    ) -> Result<Vec<DeploymentSummary>, DeploymentApiError> {
+        self.persistence
+            .get_deployments(project_name, page, limit)
+            .await
+            .map_err(|e| DeploymentApiError::Internal(e.to_string()))
+    }
+
+    /// Gets the resource limits the services of a project are held to.
+    pub(crate) async fn get_resource_limits(
+        &self,
+        project: &ProjectName,
+    ) -> Result<ResourceLimits, DeploymentApiError> {
+        self.persistence
+            .get_resource_limits(project)
+            .await
+            .map_err(|e| DeploymentApiError::Internal(e.to_string()))
+    }
+
+    /// Sets the resource limits the services of a project are held to. They
+    /// apply from the next time one of its services starts.
+    pub(crate) async fn set_resource_limits(
+        &self,
+        project: &ProjectName,
+        limits: &ResourceLimits,
+    ) -> Result<(), DeploymentApiError> {
+        if limits.cpu_percent.is_some() && !self.cpu_limits {
+            return Err(DeploymentApiError::BadRequest(
+                "cpu limits need the api to be started with a cgroup for runners".to_string(),
+            ));
+        }
+
+        self.persistence
+            .set_resource_limits(project, limits)
+            .await
+            .map_err(|e| DeploymentApiError::Internal(e.to_string()))
+    }
+
     pub(crate) async fn kill_deployment_for_project(
         &self,
         project_name: &ProjectName,
     ) -> Result<DeploymentMeta, DeploymentApiError> {
         let id = self.get_deployment_for_project(project_name).await?.id;
-        self.kill_deployment(&id).await
+        let meta = self.kill_deployment(&id).await?;
+
+        self.tcp_proxy.release(project_name).await;
+
+        Ok(meta)
     }
 
     /// Remove a deployment from the deployments hash map and, if it has
-    /// already been deployed, kill the Tokio task in which it is running
-    /// and deallocate the linked library.
+    /// already been deployed, shut down the service running it and
+    /// deallocate the linked library.
     pub(crate) async fn kill_deployment(
         &self,
         id: &DeploymentId,
     ) -> Result<DeploymentMeta, DeploymentApiError> {
-        match self.deployments.write().await.remove(id) {
+        let deployment = self.deployments.write().await.remove(id);
+
+        match deployment {
             Some(deployment) => {
                 let mut meta = deployment.meta().await;
 
-                // If the deployment is in the 'deployed' state, kill the Tokio
-                // task in which it is deployed and deallocate the linked
-                // library when the runtime gets around to it.
-
-                let mut lock = deployment.state.write().await;
-                if let DeploymentState::Deployed(DeployedState { so, handle, .. }) = lock.take() {
-                    handle.abort();
-
-                    tokio::spawn(async move {
-                        so.close().unwrap();
-                    });
-
-                    match handle.await {
-                        Err(err) if err.is_cancelled() => {}
-                        other => other
-                            .map_err(|e| DeploymentApiError::Internal(e.to_string()))?
-                            .map_err(|e| DeploymentApiError::Internal(e.to_string()))?,
-                    };
+                if let Err(e) = deployment.stop(self.timeouts.drain).await {
+                    error!("failed to stop deployment '{}': {}", meta.id, e);
                 }
 
-                let _ = self.router.remove(&meta.host);
+                self.router.remove(&meta.host).await;
+                if let Some(tcp_port) = meta.tcp_port {
+                    self.tcp_proxy.stop(tcp_port).await;
+                }
 
                 meta.state = DeploymentStateMeta::Deleted;
+                self.persistence
+                    .update_state(&meta.id, &meta.state)
+                    .await
+                    .map_err(|e| DeploymentApiError::Internal(e.to_string()))?;
 
                 Ok(meta)
             }
@@ -549,12 +1137,84 @@ impl DeploymentSystem {
         .await
     }
 
+    /// Starts a new deployment for a project from the build of one of its
+    /// earlier deployments. The build is not redone: the new deployment starts
+    /// off as built and gets promoted once it is deployed.
+    pub(crate) async fn rollback(
+        &self,
+        project: &ProjectName,
+        id: &DeploymentId,
+    ) -> Result<DeploymentMeta, DeploymentApiError> {
+        if self.num_active().await >= MAX_DEPLOYS {
+            return Err(DeploymentApiError::Unavailable(
+                "this instance has reached its maximum number of supported deployments".to_string(),
+            ));
+        };
+
+        let target = self.get_deployment(id).await?;
+        if target.project != *project {
+            return Err(DeploymentApiError::NotFound(format!(
+                "could not find deployment for id '{}'",
+                &id
+            )));
+        }
+
+        let so_path = self
+            .persistence
+            .get_so_path(id)
+            .await
+            .map_err(|e| DeploymentApiError::Internal(e.to_string()))?
+            .ok_or_else(|| {
+                DeploymentApiError::BadRequest(format!(
+                    "deployment '{}' was never built so it cannot be rolled back to",
+                    &id
+                ))
+            })?;
+
+        let build = Build::reuse(&so_path).map_err(|e| {
+            DeploymentApiError::BadRequest(format!(
+                "the build of deployment '{}' is no longer available: {}",
+                &id, e
+            ))
+        })?;
+
+        let mut meta = DeploymentMeta::built(&self.fqdn, project.clone());
+        meta.log_level = target.log_level;
+        meta.protocol = target.protocol;
+        meta.tcp_port = target.tcp_port;
+        meta.health_path = target.health_path;
+        meta.build_logs = Some(format!("Rolled back to the build of deployment {}\n", id));
+
+        self.persistence
+            .insert_deployment(&meta)
+            .await
+            .map_err(|e| DeploymentApiError::Internal(e.to_string()))?;
+        self.persistence
+            .set_so_path(&meta.id, &build.so_path)
+            .await
+            .map_err(|e| DeploymentApiError::Internal(e.to_string()))?;
+
+        let deployment = Arc::new(Deployment::new(meta.clone(), DeploymentState::built(build)));
+
+        self.deployments
+            .write()
+            .await
+            .insert(meta.id, deployment.clone());
+
+        self.job_queue.push(deployment).await;
+
+        Ok(meta)
+    }
+
     /// Main way to interface with the deployment manager.
     /// Will take a crate through the whole lifecycle.
     pub(crate) async fn deploy(
         &self,
         crate_file: Data<'_>,
         project: ProjectName,
+        log_level: LevelFilter,
+        protocol: Protocol,
+        health_path: Option<String>,
     ) -> Result<DeploymentMeta, DeploymentApiError> {
         // Assumes that only `::Deployed` deployments are blocking a thread.
         if self.num_active().await >= MAX_DEPLOYS {
@@ -563,6 +1223,25 @@ impl DeploymentSystem {
             ));
         };
 
+        let tcp_port = match protocol {
+            Protocol::Http => None,
+            Protocol::Tcp if !self.tcp_proxy.is_enabled() => {
+                return Err(DeploymentApiError::BadRequest(
+                    "this instance does not support services taking raw TCP connections"
+                        .to_string(),
+                ))
+            }
+            Protocol::Tcp => {
+                let tcp_port = self.tcp_proxy.port_for_project(&project).await;
+
+                Some(tcp_port.ok_or_else(|| {
+                    DeploymentApiError::Unavailable(
+                        "this instance has run out of public TCP ports".to_string(),
+                    )
+                })?)
+            }
+        };
+
         let crate_bytes = crate_file
             .open(ByteUnit::max_value())
             .into_bytes()
@@ -572,10 +1251,22 @@ impl DeploymentSystem {
             })?
             .to_vec();
 
-        let deployment = Arc::new(Deployment::from_bytes(&self.fqdn, project, crate_bytes));
+        let deployment = Arc::new(Deployment::from_bytes(
+            &self.fqdn,
+            project,
+            crate_bytes,
+            log_level,
+            tcp_port,
+            health_path,
+        ));
 
         let info = deployment.meta().await;
 
+        self.persistence
+            .insert_deployment(&info)
+            .await
+            .map_err(|e| DeploymentApiError::Internal(e.to_string()))?;
+
         self.deployments
             .write()
             .await
@@ -587,6 +1278,146 @@ impl DeploymentSystem {
     }
 }
 
+/// Waits for a freshly started service to be ready to take requests: for its
+/// port to accept connections or, if it has a health path, for a `GET` of
+/// that path to answer with a 2xx status.
+async fn wait_until_ready(
+    port: Port,
+    health_path: Option<&str>,
+    timeout: Duration,
+) -> anyhow::Result<()> {
+    let check = async {
+        while probe(port, health_path, timeout).await.is_err() {
+            tokio::time::sleep(READINESS_INTERVAL).await;
+        }
+    };
+
+    tokio::time::timeout(timeout, check).await.map_err(|_| {
+        anyhow::anyhow!(
+            "service was not ready to take requests within {} seconds",
+            timeout.as_secs()
+        )
+    })
+}
+
+/// Checks once whether a service takes requests, the same way as
+/// [`wait_until_ready`] does.
+async fn probe(port: Port, health_path: Option<&str>, timeout: Duration) -> anyhow::Result<()> {
+    let check = async {
+        match health_path {
+            Some(path) => {
+                let uri: Uri =
+                    format!("http://{}:{}{}", Ipv4Addr::LOCALHOST, port, path).parse()?;
+                let status = Client::new().get(uri).await?.status();
+
+                if !status.is_success() {
+                    anyhow::bail!("health path answered with {}", status);
+                }
+            }
+            None => {
+                tokio::net::TcpStream::connect((Ipv4Addr::LOCALHOST, port)).await?;
+            }
+        }
+
+        Ok::<_, anyhow::Error>(())
+    };
+
+    tokio::time::timeout(timeout, check)
+        .await
+        .map_err(|_| anyhow::anyhow!("service did not answer in time"))?
+}
+
+/// What a check on a deployed service found.
+enum Health {
+    /// The service is running and, if it takes requests, answering
+    Healthy,
+    /// The service is running but does not answer
+    Unresponsive(anyhow::Error),
+    /// The service stopped running
+    Crashed(anyhow::Error),
+    /// There is nothing to check on anymore, like when the deployment got
+    /// stopped or its service finished its work
+    Gone,
+}
+
+/// Watches over a deployed service for as long as it is deployed, restarting
+/// it with a backoff whenever it crashes.
+async fn monitor(deployment: Arc<Deployment>, context: Arc<Context>) {
+    let id = deployment.meta().await.id;
+    let mut probe_failures = 0;
+    let mut crashes = 0;
+    let mut running_since = Instant::now();
+
+    loop {
+        tokio::time::sleep(HEALTH_INTERVAL).await;
+
+        deployment.record_usage().await;
+
+        let error = match deployment.check_health().await {
+            Health::Healthy => {
+                probe_failures = 0;
+                continue;
+            }
+            Health::Unresponsive(e) => {
+                probe_failures += 1;
+                if probe_failures < HEALTH_PROBE_FAILURES {
+                    continue;
+                }
+                e
+            }
+            Health::Crashed(e) => e,
+            Health::Gone => break,
+        };
+
+        warn!("deployment '{}' crashed: {:#}", id, error);
+        deployment.crash(error, &context).await;
+
+        // Only crashes in quick succession back off further
+        if running_since.elapsed() > MAX_RESTART_BACKOFF {
+            crashes = 0;
+        }
+
+        let mut error = None;
+        loop {
+            let backoff = RESTART_BACKOFF
+                .checked_mul(2u32.saturating_pow(crashes))
+                .map_or(MAX_RESTART_BACKOFF, |backoff| {
+                    backoff.min(MAX_RESTART_BACKOFF)
+                });
+            crashes += 1;
+
+            if let Some(error) = error.take() {
+                deployment.crash(error, &context).await;
+            }
+
+            tokio::time::sleep(backoff).await;
+
+            // Stopped or replaced in the meantime
+            if !context.deployments.read().await.contains_key(&id) {
+                return;
+            }
+
+            debug!("restarting deployment '{}'", id);
+            match deployment.restart(&context).await {
+                Ok(()) => break,
+                Err(e) => {
+                    warn!("deployment '{}' failed to restart: {:#}", id, e);
+                    error = Some(e);
+                }
+            }
+        }
+
+        if !deployment.deployment_active().await {
+            break;
+        }
+
+        probe_failures = 0;
+        running_since = Instant::now();
+    }
+
+    debug!("stopped monitoring deployment '{}'", id);
+}
+
 /// Call on the operating system to identify an available port on which a
 /// deployment may then be hosted.
 fn identify_free_port() -> u16 {
@@ -601,14 +1432,16 @@ enum DeploymentState {
     Queued(QueuedState),
     /// Built deployment that is ready and waiting to be loaded.
     Built(BuiltState),
-    /// Deployment is loaded into the server application as a
-    /// dynamically-linked library (`.so` file). The [`libloading`] crate has
-    /// been used to achieve this and to obtain this particular deployment's
+    /// Deployment is being loaded by a runner process of its own, which
+    /// dynamically links its shared object (`.so` file) and starts its
     /// implementation of the [`shuttle_service::Service`] trait.
-    Loaded(Loader),
-    /// Deployment that is actively running inside a Tokio task and listening
-    /// for connections on some port indicated in [`DeployedState`].
+    Loaded(LoadedState),
+    /// Deployment that is actively running inside its runner process and,
+    /// unless it is a background worker, listening for connections on some
+    /// port indicated in [`DeployedState`].
     Deployed(DeployedState),
+    /// Deployed service which stopped running, waiting to be restarted.
+    Crashed(String),
     /// A state entered when something unexpected occurs during the deployment
     /// process.
     Error(anyhow::Error),
@@ -631,12 +1464,8 @@ impl DeploymentState {
         Self::Built(BuiltState { build })
     }
 
-    fn loaded(loader: Loader) -> Self {
-        Self::Loaded(loader)
-    }
-
-    fn deployed(so: Library, port: Port, handle: ServeHandle) -> Self {
-        Self::Deployed(DeployedState { so, port, handle })
+    fn loaded(loaded: LoadedState) -> Self {
+        Self::Loaded(loaded)
     }
 
     fn meta(&self) -> DeploymentStateMeta {
@@ -645,6 +1474,7 @@ impl DeploymentState {
             DeploymentState::Built(_) => DeploymentStateMeta::Built,
             DeploymentState::Loaded(_) => DeploymentStateMeta::Loaded,
             DeploymentState::Deployed(_) => DeploymentStateMeta::Deployed,
+            DeploymentState::Crashed(e) => DeploymentStateMeta::Crashed(e.clone()),
             DeploymentState::Error(e) => DeploymentStateMeta::Error(format!("{:#?}", e)),
             DeploymentState::Deleted => DeploymentStateMeta::Deleted,
         }
@@ -659,9 +1489,340 @@ struct BuiltState {
     build: Build,
 }
 
-#[allow(dead_code)]
-struct DeployedState {
-    so: Library,
+struct LoadedState {
+    runner: Runner,
     port: Port,
-    handle: ServeHandle,
+    /// Reported by the runner once the service got started
+    started: oneshot::Receiver<Started>,
+}
+
+struct DeployedState {
+    runner: Runner,
+    /// Not set for services which take no requests
+    port: Option<Port>,
+}
+
+impl DeployedState {
+    /// Asks the service to stop taking new requests and gives it `drain_timeout`
+    /// to finish the ones it is handling, after which its runner is killed.
+    async fn stop(self, drain_timeout: Duration) -> Result<(), DeploymentApiError> {
+        self.runner
+            .stop(drain_timeout)
+            .await
+            .map_err(|e| DeploymentApiError::Internal(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::net::Ipv4Addr;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::{Path, PathBuf};
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use rocket::tokio;
+    use shuttle_common::project::ProjectName;
+    use shuttle_common::{BuildEvent, DeploymentMeta, DeploymentStateMeta, ResourceLimits};
+    use tokio::sync::mpsc::{self, UnboundedReceiver};
+    use uuid::Uuid;
+
+    use super::{BuildLog, Context, DeployedState, Deployment, DeploymentState, Health, Timeouts};
+    use crate::build::{Build, BuildSystem};
+    use crate::persistence::Persistence;
+    use crate::router::Router;
+    use crate::runner::{Runner, RunnerConfig, Secrets, Starts};
+    use crate::tcp::TcpProxy;
+
+    /// Deployments in these tests only ever get restarted from their build.
+    struct NoBuilds;
+
+    #[async_trait]
+    impl BuildSystem for NoBuilds {
+        async fn build(
+            &self,
+            _crate_bytes: &[u8],
+            _project: &str,
+            _buf: Box<dyn std::io::Write + Send>,
+        ) -> anyhow::Result<Build> {
+            Err(anyhow::anyhow!("no builds in tests"))
+        }
+
+        fn fs_root(&self) -> PathBuf {
+            std::env::temp_dir()
+        }
+    }
+
+    /// A directory with a stand-in for a shared object and for the runner.
+    struct Fixture {
+        dir: PathBuf,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("shuttle-api-{}", Uuid::new_v4()));
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("service.so"), b"").unwrap();
+
+            Self { dir }
+        }
+
+        fn so_path(&self) -> PathBuf {
+            self.dir.join("service.so")
+        }
+
+        /// A runner which runs the shell `script` in place of a service.
+        fn runner(&self, name: &str, script: &str) -> RunnerConfig {
+            let path = self.dir.join(name);
+            fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+
+            RunnerConfig {
+                path,
+                cgroup: None,
+                uid: None,
+                gid: None,
+                supervisor_port: 0,
+                provisioner_uri: "http://localhost:5001".to_string(),
+            }
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    async fn context(runners: RunnerConfig) -> (Context, UnboundedReceiver<BuildLog>) {
+        let (build_logs_tx, build_logs_rx) = mpsc::unbounded_channel();
+
+        let context = Context {
+            router: Arc::new(Router::default()),
+            tcp_proxy: Arc::new(TcpProxy::new(
+                Ipv4Addr::LOCALHOST.into(),
+                None,
+                Default::default(),
+            )),
+            build_system: Box::new(NoBuilds),
+            deployments: Default::default(),
+            runners,
+            starts: Starts::default(),
+            secrets: Secrets::default(),
+            persistence: Persistence::new_in_memory().await.unwrap(),
+            build_logs_tx,
+            timeouts: Timeouts {
+                readiness: Duration::from_secs(5),
+                drain: Duration::from_secs(1),
+            },
+        };
+
+        (context, build_logs_rx)
+    }
+
+    /// A deployment whose service runs in a runner started from `config`.
+    async fn deployed(
+        config: &RunnerConfig,
+        so_path: &Path,
+        port: Option<u16>,
+        context: &Context,
+    ) -> Arc<Deployment> {
+        let meta = DeploymentMeta::built(
+            "test.shuttleapp.rs",
+            ProjectName::from_str("monitored").unwrap(),
+        );
+        context.persistence.insert_deployment(&meta).await.unwrap();
+        context
+            .persistence
+            .set_so_path(&meta.id, so_path)
+            .await
+            .unwrap();
+
+        let runner = Runner::spawn(
+            config,
+            so_path,
+            &meta,
+            ResourceLimits::default(),
+            0,
+            &context.secrets,
+        )
+        .unwrap();
+        let deployment = Arc::new(Deployment::new(
+            meta.clone(),
+            DeploymentState::Deployed(DeployedState { runner, port }),
+        ));
+        context
+            .deployments
+            .write()
+            .await
+            .insert(meta.id, deployment.clone());
+
+        deployment
+    }
+
+    async fn wait_for_exit(deployment: &Deployment) {
+        for _ in 0..100 {
+            if let DeploymentState::Deployed(deployed) = &mut *deployment.state.write().await {
+                if deployed.runner.exit_status().unwrap().is_some() {
+                    return;
+                }
+            }
+
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        panic!("runner did not exit");
+    }
+
+    fn closed_port() -> u16 {
+        std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port()
+    }
+
+    #[tokio::test]
+    async fn check_health() {
+        let fixture = Fixture::new();
+        let running = fixture.runner("running", "exec sleep 30");
+        let failing = fixture.runner("failing", "exit 3");
+        let finished = fixture.runner("finished", "exit 0");
+        let (context, _build_logs) = context(running.clone()).await;
+        let so_path = fixture.so_path();
+
+        let worker = deployed(&running, &so_path, None, &context).await;
+        assert!(matches!(worker.check_health().await, Health::Healthy));
+
+        let unresponsive = deployed(&running, &so_path, Some(closed_port()), &context).await;
+        assert!(matches!(
+            unresponsive.check_health().await,
+            Health::Unresponsive(_)
+        ));
+
+        let crashed = deployed(&failing, &so_path, None, &context).await;
+        wait_for_exit(&crashed).await;
+        match crashed.check_health().await {
+            Health::Crashed(e) => assert_eq!(e.to_string(), "service exited with exit status: 3"),
+            _ => panic!("a runner exiting with an error should count as crashed"),
+        }
+
+        // Background workers may be done with their work
+        let done = deployed(&finished, &so_path, None, &context).await;
+        wait_for_exit(&done).await;
+        assert!(matches!(done.check_health().await, Health::Gone));
+
+        let stopped = deployed(&finished, &so_path, Some(closed_port()), &context).await;
+        wait_for_exit(&stopped).await;
+        assert!(matches!(stopped.check_health().await, Health::Crashed(_)));
+
+        worker.stop(Duration::ZERO).await.unwrap();
+        assert!(matches!(worker.check_health().await, Health::Gone));
+    }
+
+    #[tokio::test]
+    async fn crash_and_restart() {
+        let fixture = Fixture::new();
+        let failing = fixture.runner("failing", "exit 3");
+        let (context, mut build_logs) = context(fixture.runner("running", "exec sleep 30")).await;
+
+        let deployment = deployed(&failing, &fixture.so_path(), None, &context).await;
+        let id = deployment.meta().await.id;
+        wait_for_exit(&deployment).await;
+
+        let error = match deployment.check_health().await {
+            Health::Crashed(e) => e,
+            _ => panic!("runner should have crashed"),
+        };
+        deployment.crash(error, &context).await;
+
+        let crashed =
+            DeploymentStateMeta::Crashed("service exited with exit status: 3".to_string());
+        assert!(matches!(
+            build_logs.try_recv().unwrap().event,
+            BuildEvent::State(state) if state == crashed
+        ));
+        let stored = context
+            .persistence
+            .get_deployment(&id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.state, crashed);
+        assert_eq!(
+            stored.last_crash.as_deref(),
+            Some("service exited with exit status: 3")
+        );
+
+        // Stands in for the runner reporting back, as the supervisor would
+        let starts = context.starts.clone();
+        tokio::spawn(async move {
+            while starts.report(&id, Ok(false)).await.is_err() {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        });
+
+        deployment.restart(&context).await.unwrap();
+
+        assert!(deployment.deployment_active().await);
+        assert!(matches!(deployment.check_health().await, Health::Healthy));
+        let stored = context
+            .persistence
+            .get_deployment(&id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.state, DeploymentStateMeta::Deployed);
+        assert_eq!(stored.restarts, 1);
+
+        deployment.stop(Duration::ZERO).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn failed_restart() {
+        let fixture = Fixture::new();
+        let failing = fixture.runner("failing", "exit 3");
+        let (context, _build_logs) = context(failing.clone()).await;
+
+        let deployment = deployed(&failing, &fixture.so_path(), None, &context).await;
+        let id = deployment.meta().await.id;
+        wait_for_exit(&deployment).await;
+
+        deployment
+            .crash(anyhow::anyhow!("runner exited"), &context)
+            .await;
+
+        let error = deployment.restart(&context).await.unwrap_err();
+
+        assert!(
+            error.to_string().contains("before its service started"),
+            "unexpected error: {}",
+            error
+        );
+        let stored = context
+            .persistence
+            .get_deployment(&id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(matches!(stored.state, DeploymentStateMeta::Error(_)));
+        assert_eq!(stored.restarts, 1);
+
+        // Nothing is left to restart once the deployment is stopped
+        deployment.stop(Duration::ZERO).await.unwrap();
+        deployment.restart(&context).await.unwrap();
+        assert_eq!(
+            context
+                .persistence
+                .get_deployment(&id)
+                .await
+                .unwrap()
+                .unwrap()
+                .restarts,
+            1
+        );
+    }
 }
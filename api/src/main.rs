@@ -9,32 +9,90 @@ mod auth;
 mod auth_admin;
 mod build;
 mod deployment;
-mod factory;
+mod persistence;
 mod proxy;
 mod router;
+mod runner;
+mod tcp;
 
 use std::collections::HashMap;
 use std::net::IpAddr;
+use std::str::FromStr;
 use std::sync::Arc;
 
 use auth_admin::Admin;
+use chrono::{DateTime, Duration, Utc};
 use clap::Parser;
 use deployment::MAX_DEPLOYS;
-use factory::ShuttleFactory;
+use log::{Level, LevelFilter};
+use rocket::response::stream::{Event, EventStream};
 use rocket::serde::json::Json;
-use rocket::{tokio, Build, Data, Rocket, State};
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket::{tokio, Build, Data, Rocket, Shutdown, State};
+use shuttle_common::database::{self, AwsRdsEngine};
 use shuttle_common::project::ProjectName;
-use shuttle_common::{DeploymentApiError, DeploymentMeta, Port};
+use shuttle_common::resource;
+use shuttle_common::{
+    BuildEvent, DeploymentApiError, DeploymentMeta, DeploymentStateMeta, DeploymentSummary,
+    LogFilter, Port, Protocol, ResourceLimits, RuntimeLog,
+};
 use shuttle_service::SecretStore;
 use uuid::Uuid;
 
 use crate::args::Args;
 use crate::auth::{ApiKey, AuthorizationError, ScopedUser, User, UserDirectory};
 use crate::build::{BuildSystem, FsBuildSystem};
-use crate::deployment::DeploymentSystem;
+use crate::deployment::{DeploymentSystem, Timeouts};
+use crate::persistence::{LogRetention, Persistence};
+use crate::runner::RunnerConfig;
+use crate::tcp::TcpProxy;
 
 type ApiResult<T, E> = Result<Json<T>, E>;
 
+/// Number of deployments returned per page when listing a project's history
+const DEFAULT_PAGE_LIMIT: u32 = 10;
+const MAX_PAGE_LIMIT: u32 = 100;
+
+/// Number of runtime logs returned per page when getting a project's logs
+const DEFAULT_LOG_PAGE_LIMIT: u32 = 100;
+const MAX_LOG_PAGE_LIMIT: u32 = 1000;
+
+/// Query parameters for picking out runtime logs, see [`LogFilter`]
+#[derive(FromForm)]
+struct LogQuery {
+    level: Option<String>,
+    target: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+}
+
+impl TryFrom<LogQuery> for LogFilter {
+    type Error = DeploymentApiError;
+
+    fn try_from(query: LogQuery) -> Result<Self, Self::Error> {
+        let level = query
+            .level
+            .map(|level| Level::from_str(&level))
+            .transpose()
+            .map_err(|_| DeploymentApiError::BadRequest("invalid log level".to_string()))?;
+
+        Ok(LogFilter {
+            level,
+            target: query.target,
+            since: query.since.map(parse_datetime).transpose()?,
+            until: query.until.map(parse_datetime).transpose()?,
+        })
+    }
+}
+
+fn parse_datetime(datetime: String) -> Result<DateTime<Utc>, DeploymentApiError> {
+    DateTime::parse_from_rfc3339(&datetime)
+        .map(|datetime| datetime.with_timezone(&Utc))
+        .map_err(|e| {
+            DeploymentApiError::BadRequest(format!("invalid timestamp '{}': {}", datetime, e))
+        })
+}
+
 /// Find user by username and return it's API Key.
 /// if user does not exist create it and update `users` state to `users.toml`.
 /// Finally return user's API Key.
@@ -47,6 +105,41 @@ async fn get_or_create_user(
     user_directory.get_or_create(username)
 }
 
+/// Get the resource limits the services of a project are held to.
+#[get("/limits/<project_name>")]
+async fn get_resource_limits(
+    state: &State<ApiState>,
+    project_name: ProjectName,
+    _admin: Admin,
+) -> ApiResult<ResourceLimits, DeploymentApiError> {
+    let limits = state
+        .deployment_manager
+        .get_resource_limits(&project_name)
+        .await?;
+
+    Ok(Json(limits))
+}
+
+/// Set the resource limits the services of a project are held to. A running
+/// service is only held to them once it starts again, like when it is
+/// redeployed.
+#[put("/limits/<project_name>", data = "<limits>")]
+async fn set_resource_limits(
+    state: &State<ApiState>,
+    project_name: ProjectName,
+    limits: Json<ResourceLimits>,
+    _admin: Admin,
+) -> ApiResult<ResourceLimits, DeploymentApiError> {
+    info!("[SET_RESOURCE_LIMITS, {}]", &project_name);
+
+    state
+        .deployment_manager
+        .set_resource_limits(&project_name, &limits)
+        .await?;
+
+    Ok(limits)
+}
+
 /// Status API to be used to check if the service is alive
 #[get("/status")]
 async fn status() -> String {
@@ -58,6 +151,31 @@ async fn version() -> String {
     String::from(shuttle_service::VERSION)
 }
 
+#[get("/<_>/deployments?<page>&<limit>")]
+async fn get_deployments(
+    state: &State<ApiState>,
+    page: Option<u32>,
+    limit: Option<u32>,
+    user: ScopedUser,
+) -> ApiResult<Vec<DeploymentSummary>, DeploymentApiError> {
+    info!("[GET_DEPLOYMENTS, {}, {}]", user.name(), user.scope());
+
+    let page = page.unwrap_or(0);
+    let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).min(MAX_PAGE_LIMIT);
+    if page.checked_mul(limit).is_none() {
+        return Err(DeploymentApiError::BadRequest(
+            "page is out of range".to_string(),
+        ));
+    }
+
+    let deployments = state
+        .deployment_manager
+        .get_deployments_for_project(user.scope(), page, limit)
+        .await?;
+
+    Ok(Json(deployments))
+}
+
 #[get("/<_>/deployments/<id>")]
 async fn get_deployment(
     state: &State<ApiState>,
@@ -82,6 +200,101 @@ async fn delete_deployment(
     Ok(Json(deployment))
 }
 
+#[post("/<_>/deployments/<id>/rollback")]
+async fn rollback_deployment(
+    state: &State<ApiState>,
+    id: Uuid,
+    user: ScopedUser,
+) -> ApiResult<DeploymentMeta, DeploymentApiError> {
+    info!("[ROLLBACK_DEPLOYMENT, {}, {}]", user.name(), user.scope());
+
+    let deployment = state.deployment_manager.rollback(user.scope(), &id).await?;
+
+    Ok(Json(deployment))
+}
+
+/// Streams the build output of a deployment as server-sent events, marking
+/// every stage it moves through. The output it already has is sent first and
+/// the stream ends once the deployment is done.
+#[get("/<_>/deployments/<id>/build/stream")]
+async fn stream_build(
+    state: &State<ApiState>,
+    id: Uuid,
+    user: ScopedUser,
+    mut shutdown: Shutdown,
+) -> Result<EventStream![], DeploymentApiError> {
+    info!("[STREAM_BUILD, {}, {}]", user.name(), user.scope());
+
+    let deployment_manager = state.deployment_manager.clone();
+    let (deployment, events) = deployment_manager.subscribe_build(&id).await?;
+    if deployment.project != *user.scope() {
+        return Err(DeploymentApiError::NotFound(format!(
+            "could not find deployment for id '{}'",
+            &id
+        )));
+    }
+
+    Ok(EventStream! {
+        for line in deployment.build_logs.iter().flat_map(|logs| logs.lines()) {
+            yield Event::json(&BuildEvent::Line(line.to_string()));
+        }
+
+        yield Event::json(&BuildEvent::State(deployment.state.clone()));
+
+        // Nothing more will come for deployments that are done
+        if let Some(mut events) = events.filter(|_| !is_done(&deployment.state)) {
+            loop {
+                let log = tokio::select! {
+                    log = events.recv() => match log {
+                        Ok(log) => log,
+                        Err(RecvError::Closed) => break,
+                        Err(RecvError::Lagged(skipped)) => {
+                            warn!("build stream for '{}' skipped {} events", id, skipped);
+
+                            // The skipped events may have held the state the deployment ended up in
+                            match deployment_manager.get_deployment(&id).await {
+                                Ok(current) => {
+                                    yield Event::json(&BuildEvent::State(current.state.clone()));
+
+                                    if is_done(&current.state) {
+                                        break;
+                                    }
+                                }
+                                Err(e) => warn!("failed to get the state of '{}': {}", id, e),
+                            }
+
+                            continue;
+                        }
+                    },
+                    _ = &mut shutdown => break,
+                };
+
+                if log.deployment_id != id {
+                    continue;
+                }
+
+                let done = matches!(&log.event, BuildEvent::State(state) if is_done(state));
+
+                yield Event::json(&log.event);
+
+                if done {
+                    break;
+                }
+            }
+        }
+    })
+}
+
+/// Whether a deployment has reached a stage it will not move on from.
+fn is_done(state: &DeploymentStateMeta) -> bool {
+    matches!(
+        state,
+        DeploymentStateMeta::Deployed
+            | DeploymentStateMeta::Error(_)
+            | DeploymentStateMeta::Deleted
+    )
+}
+
 #[get("/<_>")]
 async fn get_project(
     state: &State<ApiState>,
@@ -97,6 +310,108 @@ async fn get_project(
     Ok(Json(deployment))
 }
 
+/// Gets one page of the runtime logs of the latest deployment of a project
+/// which match the query, oldest first. Pass the id of the last log of a
+/// page as `after` to get the next one.
+#[get("/<_>/logs?<after>&<limit>&<query..>")]
+async fn get_logs(
+    state: &State<ApiState>,
+    after: Option<i64>,
+    limit: Option<u32>,
+    query: LogQuery,
+    user: ScopedUser,
+) -> ApiResult<Vec<RuntimeLog>, DeploymentApiError> {
+    info!("[GET_LOGS, {}, {}]", user.name(), user.scope());
+
+    let filter = LogFilter::try_from(query)?;
+    let limit = limit
+        .unwrap_or(DEFAULT_LOG_PAGE_LIMIT)
+        .min(MAX_LOG_PAGE_LIMIT);
+    let deployment = state
+        .deployment_manager
+        .get_deployment_for_project(user.scope())
+        .await?;
+
+    let logs = state
+        .deployment_manager
+        .get_logs(&deployment.id, &filter, after, limit)
+        .await?;
+
+    Ok(Json(logs))
+}
+
+/// Streams the runtime logs of a project which match the query as server-sent
+/// events. The logs the latest deployment already has are sent first, then the
+/// logs of whichever deployments the project runs as they come in, so that the
+/// stream carries on across redeploys.
+#[get("/<_>/logs/stream?<query..>")]
+async fn stream_logs(
+    state: &State<ApiState>,
+    query: LogQuery,
+    user: ScopedUser,
+    mut shutdown: Shutdown,
+) -> Result<EventStream![], DeploymentApiError> {
+    info!("[STREAM_LOGS, {}, {}]", user.name(), user.scope());
+
+    let filter = LogFilter::try_from(query)?;
+    let deployment_manager = state.deployment_manager.clone();
+
+    // Subscribe before getting the existing logs so that none get lost in between
+    let mut logs = deployment_manager.subscribe_logs();
+    let deployment = deployment_manager
+        .get_deployment_for_project(user.scope())
+        .await?;
+
+    Ok(EventStream! {
+        let mut last_sent = None;
+        let mut caught_up = false;
+
+        while !caught_up {
+            let page = match deployment_manager
+                .get_logs(&deployment.id, &filter, last_sent, MAX_LOG_PAGE_LIMIT)
+                .await
+            {
+                Ok(page) => page,
+                Err(e) => {
+                    warn!("failed to get stored logs of '{}': {}", deployment.id, e);
+                    break;
+                }
+            };
+
+            caught_up = page.len() < MAX_LOG_PAGE_LIMIT as usize;
+
+            for log in page {
+                last_sent = Some(log.id);
+                yield Event::json(&log);
+            }
+        }
+
+        // Going live without all stored logs would leave a gap
+        if caught_up {
+            loop {
+                let log = tokio::select! {
+                    log = logs.recv() => match log {
+                        Ok(log) => log,
+                        Err(RecvError::Closed) => break,
+                        Err(RecvError::Lagged(skipped)) => {
+                            warn!("log stream for '{}' skipped {} logs", deployment.project, skipped);
+                            continue;
+                        }
+                    },
+                    _ = &mut shutdown => break,
+                };
+
+                if log.project == deployment.project
+                    && last_sent.map_or(true, |last_sent| log.log.id > last_sent)
+                    && filter.matches(&log.log.datetime, &log.log.item)
+                {
+                    yield Event::json(&log.log);
+                }
+            }
+        }
+    })
+}
+
 #[delete("/<_>")]
 async fn delete_project(
     state: &State<ApiState>,
@@ -111,16 +426,42 @@ async fn delete_project(
     Ok(Json(deployment))
 }
 
-#[post("/<project_name>", data = "<crate_file>")]
+#[post(
+    "/<project_name>?<log_level>&<protocol>&<health_path>",
+    data = "<crate_file>"
+)]
 async fn create_project(
     state: &State<ApiState>,
     user_directory: &State<UserDirectory>,
     crate_file: Data<'_>,
     project_name: ProjectName,
+    log_level: Option<String>,
+    protocol: Option<String>,
+    health_path: Option<String>,
     user: User,
 ) -> ApiResult<DeploymentMeta, DeploymentApiError> {
     info!("[CREATE_PROJECT, {}, {}]", &user.name, &project_name);
 
+    let log_level = log_level
+        .map(|level| LevelFilter::from_str(&level))
+        .transpose()
+        .map_err(|_| DeploymentApiError::BadRequest("invalid log level".to_string()))?
+        .unwrap_or(LevelFilter::Info);
+
+    let protocol = protocol
+        .map(|protocol| Protocol::from_str(&protocol))
+        .transpose()
+        .map_err(|_| DeploymentApiError::BadRequest("invalid protocol".to_string()))?
+        .unwrap_or_default();
+
+    if let Some(health_path) = &health_path {
+        if !health_path.starts_with('/') {
+            return Err(DeploymentApiError::BadRequest(
+                "health path has to start with `/`".to_string(),
+            ));
+        }
+    }
+
     if !user
         .projects
         .iter()
@@ -130,7 +471,7 @@ async fn create_project(
     }
     let deployment = state
         .deployment_manager
-        .deploy(crate_file, project_name)
+        .deploy(crate_file, project_name, log_level, protocol, health_path)
         .await?;
     Ok(Json(deployment))
 }
@@ -149,9 +490,19 @@ async fn project_secrets(
         .get_deployment_for_project(user.scope())
         .await?;
 
-    if let Some(database_deployment) = &deployment.database_deployment {
-        let conn_str = database_deployment.connection_string_private();
-        let conn = sqlx::PgPool::connect(&conn_str)
+    // Secrets are kept in the unnamed Postgres database of the service
+    let postgres = deployment.resources.iter().find(|resource| {
+        resource.name.is_none()
+            && matches!(
+                resource.r#type,
+                resource::Type::Database(
+                    database::Type::Shared | database::Type::AwsRds(AwsRdsEngine::Postgres)
+                )
+            )
+    });
+
+    if let Some(postgres) = postgres {
+        let conn = sqlx::PgPool::connect(&postgres.connection_string_private)
             .await
             .map_err(|e| DeploymentApiError::Internal(e.to_string()))?;
 
@@ -193,13 +544,56 @@ async fn rocket() -> Rocket<Build> {
         .init();
 
     let args: Args = Args::parse();
+    if args.runner_cgroup.is_some() {
+        // SAFETY: only reads the user of this process
+        let uid = unsafe { libc::getuid() };
+        if args.runner_uid.unwrap_or(uid) == uid {
+            panic!("runners held to cpu limits need to run as a user other than the api's");
+        }
+    }
+
     let build_system = FsBuildSystem::initialise(args.path).unwrap();
+    let state_path = args
+        .state
+        .unwrap_or_else(|| build_system.fs_root().join(persistence::DEFAULT_STATE_FILE));
+    let persistence = Persistence::new(&state_path)
+        .await
+        .expect("failed to open the deployments state database");
+    let tcp_proxy = TcpProxy::new(
+        args.bind_addr,
+        args.tcp_ports,
+        persistence
+            .get_tcp_ports()
+            .await
+            .expect("failed to read TCP ports from the state database"),
+    );
     let deployment_manager = Arc::new(
         DeploymentSystem::new(
             Box::new(build_system),
             args.proxy_fqdn.to_string(),
-            args.provisioner_address,
-            args.provisioner_port,
+            RunnerConfig {
+                path: args.runner_path,
+                cgroup: args.runner_cgroup,
+                uid: args.runner_uid,
+                gid: args.runner_gid,
+                supervisor_port: args.supervisor_port,
+                provisioner_uri: format!(
+                    "http://{}:{}",
+                    args.provisioner_address, args.provisioner_port
+                ),
+            },
+            persistence,
+            LogRetention {
+                max_per_deployment: args.max_logs_per_deployment,
+                max_age: args
+                    .log_retention_hours
+                    .map(|hours| Duration::hours(hours.into())),
+            },
+            tcp_proxy,
+            Timeouts {
+                readiness: std::time::Duration::from_secs(args.readiness_timeout_secs),
+                drain: std::time::Duration::from_secs(args.drain_timeout_secs),
+            },
         )
         .await,
     );
@@ -222,13 +616,27 @@ async fn rocket() -> Rocket<Build> {
             routes![
                 delete_deployment,
                 get_deployment,
+                get_deployments,
+                rollback_deployment,
+                stream_build,
+                get_logs,
+                stream_logs,
                 delete_project,
                 create_project,
                 get_project,
                 project_secrets
             ],
         )
-        .mount("/", routes![get_or_create_user, status, version])
+        .mount(
+            "/",
+            routes![
+                get_or_create_user,
+                get_resource_limits,
+                set_resource_limits,
+                status,
+                version
+            ],
+        )
         .manage(state)
         .manage(user_directory)
 }
@@ -5,10 +5,10 @@ use std::sync::Arc;
 use ::hyper::server::conn::AddrStream;
 use ::hyper::server::Server;
 use ::hyper::service::{make_service_fn, service_fn};
-use ::hyper::{Body, Request, Response, StatusCode};
+use ::hyper::{Body, Request, Response, StatusCode, Version};
 use hyper::client::connect::dns::GaiResolver;
 use hyper::client::HttpConnector;
-use hyper::header::{HeaderValue, SERVER};
+use hyper::header::{HeaderValue, HOST, SERVER};
 use hyper::Client;
 use hyper_reverse_proxy::{ProxyError, ReverseProxy};
 use lazy_static::lazy_static;
@@ -20,6 +20,10 @@ lazy_static! {
     static ref HEADER_SERVER: HeaderValue = "shuttle.rs".parse().unwrap();
     static ref PROXY_CLIENT: ReverseProxy<HttpConnector<GaiResolver>> =
         ReverseProxy::new(Client::new());
+    // HTTP/2 requests, like gRPC calls coming in over h2c, have to stay HTTP/2 on their way to
+    // the service since they may rely on trailers
+    static ref PROXY_CLIENT_H2: ReverseProxy<HttpConnector<GaiResolver>> =
+        ReverseProxy::new(Client::builder().http2_only(true).build_http());
 }
 
 pub(crate) async fn start(
@@ -40,6 +44,8 @@ pub(crate) async fn start(
         }
     });
 
+    // Serves HTTP/1 as well as HTTP/2 with prior knowledge (h2c), which is what gRPC clients
+    // connect with
     let server = Server::bind(&socket_address).serve(make_svc);
 
     log::debug!("starting proxy server: {}", &socket_address);
@@ -56,10 +62,10 @@ async fn handle(
     req: Request<Body>,
     deployment_manager: Arc<DeploymentSystem>,
 ) -> Result<Response<Body>, Infallible> {
-    // if no `Host:` or invalid value, return 400
-    let host = match req.headers().get("Host") {
-        Some(host) if host.to_str().is_ok() => host.to_str().unwrap().to_owned(),
-        _ => {
+    // if no host or invalid value, return 400
+    let host = match request_host(&req) {
+        Some(host) => host,
+        None => {
             return Ok(Response::builder()
                 .status(StatusCode::BAD_REQUEST)
                 .body(Body::empty())
@@ -68,12 +74,12 @@ async fn handle(
     };
 
     // if we could not get a port from the deployment manager,
-    // the host does not exist or is not initialised yet - so
-    // we return a 404
+    // the host does not exist, is not initialised yet or its
+    // service takes no requests - so we return a 404
     let port = match deployment_manager.port_for_host(&host).await {
         None => {
             // no port being assigned here means that we couldn't
-            // find a service for a given host
+            // find a service taking requests for a given host
             let response_body = format!("could not find service for host: {}", host);
             return Ok(Response::builder()
                 .status(StatusCode::NOT_FOUND)
@@ -112,15 +118,100 @@ async fn handle(
     }
 }
 
+/// Gets the host a request is for. HTTP/2 requests, like the ones gRPC clients send over h2c,
+/// carry it as their `:authority` rather than in a `Host` header.
+fn request_host(req: &Request<Body>) -> Option<String> {
+    match req.headers().get(HOST) {
+        Some(host) => host.to_str().ok().map(str::to_owned),
+        None => req.uri().authority().map(ToString::to_string),
+    }
+}
+
 async fn reverse_proxy(
     ip: IpAddr,
     port: Port,
     req: Request<Body>,
 ) -> Result<Response<Body>, ProxyError> {
     let forward_uri = format!("http://127.0.0.1:{}", port);
-    let mut response = PROXY_CLIENT.call(ip, &forward_uri, req).await?;
+    let client = match req.version() {
+        Version::HTTP_2 => &*PROXY_CLIENT_H2,
+        _ => &*PROXY_CLIENT,
+    };
+    let mut response = client.call(ip, &forward_uri, req).await?;
 
     response.headers_mut().insert(SERVER, HEADER_SERVER.clone());
 
     Ok(response)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::net::{Ipv4Addr, SocketAddr};
+
+    use hyper::server::conn::AddrStream;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Client, Request, Response, Server, StatusCode};
+
+    use super::{request_host, reverse_proxy};
+
+    #[tokio::test]
+    async fn http2_prior_knowledge() {
+        // Stands in for a gRPC service, which only speaks HTTP/2
+        let service = Server::bind(&SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+            .http2_only(true)
+            .serve(make_service_fn(|_| async {
+                Ok::<_, Infallible>(service_fn(|req: Request<Body>| async move {
+                    Ok::<_, Infallible>(Response::new(Body::from(format!("{:?}", req.version()))))
+                }))
+            }));
+        let service_port = service.local_addr().port();
+        tokio::spawn(service);
+
+        // Set up like the proxy, minus looking up the port of the service from the host
+        let proxy = Server::bind(&SocketAddr::from((Ipv4Addr::LOCALHOST, 0))).serve(
+            make_service_fn(move |socket: &AddrStream| {
+                let remote_addr = socket.remote_addr();
+                async move {
+                    Ok::<_, Infallible>(service_fn(move |req: Request<Body>| async move {
+                        let host = request_host(&req).unwrap_or_default();
+                        let response =
+                            match reverse_proxy(remote_addr.ip(), service_port, req).await {
+                                Ok(response) => response,
+                                Err(_) => Response::builder()
+                                    .status(StatusCode::BAD_GATEWAY)
+                                    .body(Body::empty())
+                                    .unwrap(),
+                            };
+
+                        Ok::<_, Infallible>(
+                            Response::builder()
+                                .status(response.status())
+                                .header("x-host", host)
+                                .body(response.into_body())
+                                .unwrap(),
+                        )
+                    }))
+                }
+            }),
+        );
+        let proxy_port = proxy.local_addr().port();
+        tokio::spawn(proxy);
+
+        // Without a `Host` header, like gRPC clients over h2c
+        let client = Client::builder().http2_only(true).build_http::<Body>();
+        let response = client
+            .get(format!("http://127.0.0.1:{}/", proxy_port).parse().unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()["x-host"],
+            format!("127.0.0.1:{}", proxy_port).as_str()
+        );
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(body, "HTTP/2.0");
+    }
+}
@@ -0,0 +1,904 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::net::Ipv4Addr;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use futures::channel::oneshot;
+use rand::Rng;
+use rocket::tokio;
+use shuttle_common::resource;
+use shuttle_common::{DeploymentId, DeploymentMeta, Port, ResourceLimits, ResourceUsage};
+use shuttle_proto::provisioner::{provisioner_client::ProvisionerClient, ResourceResponse};
+use shuttle_proto::runtime::supervisor_server::Supervisor;
+use shuttle_proto::runtime::{
+    LogRequest, LogsResponse, ProvisionRequest, StartedRequest, StartedResponse, SECRET_ENV,
+    SECRET_METADATA_KEY,
+};
+use shuttle_service::logger::Log;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{Child, ChildStderr, ChildStdin, Command};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::{Mutex, RwLock};
+use tokio::task::JoinHandle;
+use tonic::transport::Channel;
+use tonic::{Request, Response, Status, Streaming};
+
+use crate::deployment::{Deployment, Deployments};
+use crate::persistence::Persistence;
+
+/// Where to find the runner binary and what runner processes need to reach
+/// the api and provisioner.
+#[derive(Clone, Debug)]
+pub(crate) struct RunnerConfig {
+    /// The `shuttle-runner` binary
+    pub(crate) path: PathBuf,
+    /// Cgroup in which each runner gets a cgroup of its own holding it to
+    /// its cpu limit
+    pub(crate) cgroup: Option<PathBuf>,
+    /// User runners run as. Only when it is not the user of the api are the
+    /// cgroups of runners out of reach of their services
+    pub(crate) uid: Option<u32>,
+    /// Group runners run as
+    pub(crate) gid: Option<u32>,
+    /// Port on localhost runners reach the api at
+    pub(crate) supervisor_port: Port,
+    /// Address of the provisioner the databases of services come from
+    pub(crate) provisioner_uri: String,
+}
+
+/// Period the cpu limit of a runner is enforced over, in microseconds. This is
+/// the default of the kernel.
+const CPU_PERIOD_US: u64 = 100_000;
+
+/// How long to wait for the last of what a runner wrote to stderr once it
+/// exited, to tell why it did.
+const STDERR_WAIT: Duration = Duration::from_secs(1);
+
+/// How often, and how long apart, to try removing the cgroup of a runner
+/// while the runner is still on its way out of it.
+const CGROUP_REMOVE_TRIES: u32 = 50;
+const CGROUP_REMOVE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Whether the service of a runner got started and, if so, whether it takes
+/// requests. See [`Service::is_routable`](shuttle_service::Service::is_routable).
+pub(crate) type Started = Result<bool, String>;
+
+/// A `shuttle-runner` process running the service of a single deployment, so
+/// that a service which crashes or misbehaves only ever takes itself down.
+pub(crate) struct Runner {
+    id: DeploymentId,
+    child: Child,
+    /// Closed to ask the service to shut down
+    stdin: Option<ChildStdin>,
+    /// Passes on what the runner writes to stderr until it exits
+    stderr: Option<JoinHandle<()>>,
+    /// Whether the service failed to allocate memory, going by stderr
+    out_of_memory: Arc<AtomicBool>,
+    limits: ResourceLimits,
+    /// Cgroup of its own holding the runner to its cpu limit
+    cgroup: Option<PathBuf>,
+    /// Authenticates the runner to the supervisor until it is dropped
+    secret: String,
+    secrets: Secrets,
+}
+
+impl Runner {
+    /// Starts a runner loading the service from `so_path` and starting it on
+    /// `port`, held to `limits`. The runner reports back whether it got
+    /// started, see [`Starts`], with a secret issued from `secrets`.
+    pub(crate) fn spawn(
+        config: &RunnerConfig,
+        so_path: &Path,
+        meta: &DeploymentMeta,
+        limits: ResourceLimits,
+        port: Port,
+        secrets: &Secrets,
+    ) -> anyhow::Result<Self> {
+        let cgroup = match limits.cpu_percent {
+            Some(cpu_percent) => Some(create_cgroup(config, meta.id, cpu_percent)?),
+            None => None,
+        };
+        let secret = secrets.issue(meta.id);
+        let mut command = Command::new(&config.path);
+
+        if let Some(memory_mb) = limits.memory_mb {
+            command.arg("--max-memory-mb").arg(memory_mb.to_string());
+        }
+        if let Some(cgroup) = &cgroup {
+            command.arg("--cgroup").arg(cgroup);
+        }
+        if let Some(open_files) = limits.open_files {
+            command.arg("--max-open-files").arg(open_files.to_string());
+        }
+        if let Some(uid) = config.uid {
+            command.uid(uid);
+        }
+        if let Some(gid) = config.gid {
+            command.gid(gid);
+        }
+
+        let spawned = command
+            .arg("--so-path")
+            .arg(so_path)
+            .arg("--deployment-id")
+            .arg(meta.id.to_string())
+            .arg("--project")
+            .arg(meta.project.as_str())
+            .arg("--port")
+            .arg(port.to_string())
+            .arg("--log-level")
+            .arg(meta.log_level.to_string())
+            .arg("--supervisor-uri")
+            .arg(format!(
+                "http://{}:{}",
+                Ipv4Addr::LOCALHOST,
+                config.supervisor_port
+            ))
+            .env(SECRET_ENV, &secret)
+            .stdin(Stdio::piped())
+            .stderr(Stdio::piped())
+            // A runner never outlives its deployment
+            .kill_on_drop(true)
+            .spawn();
+        let mut child = match spawned {
+            Ok(child) => child,
+            Err(e) => {
+                if let Some(cgroup) = &cgroup {
+                    let _ = fs::remove_dir(cgroup);
+                }
+
+                return Err(anyhow!("failed to start runner {:?}: {}", config.path, e));
+            }
+        };
+        let stdin = child.stdin.take();
+        let out_of_memory = Arc::new(AtomicBool::new(false));
+        let stderr = child
+            .stderr
+            .take()
+            .map(|stderr| tokio::spawn(watch_stderr(stderr, out_of_memory.clone())));
+
+        let runner = Self {
+            id: meta.id,
+            child,
+            stdin,
+            stderr,
+            out_of_memory,
+            limits,
+            cgroup,
+            secret,
+            secrets: secrets.clone(),
+        };
+
+        // The runner waits for this before it loads the service. Dropping it
+        // on failure kills it and removes its cgroup.
+        if let (Some(cgroup), Some(pid)) = (&runner.cgroup, runner.child.id()) {
+            fs::write(cgroup.join("cgroup.procs"), pid.to_string())
+                .map_err(|e| anyhow!("failed to move runner into cgroup {:?}: {}", cgroup, e))?;
+        }
+
+        Ok(runner)
+    }
+
+    /// Waits for the runner to report whether its service got started.
+    pub(crate) async fn started(
+        &mut self,
+        started: oneshot::Receiver<Started>,
+        timeout: Duration,
+    ) -> anyhow::Result<bool> {
+        let report = async {
+            tokio::select! {
+                started = started => match started {
+                    Ok(started) => started.map_err(|e| anyhow!(e)),
+                    Err(_) => Err(anyhow!("runner got replaced before its service started")),
+                },
+                status = self.child.wait() => Err(match status {
+                    Ok(status) => anyhow!("runner exited with {} before its service started", status),
+                    Err(e) => e.into(),
+                }),
+            }
+        };
+
+        tokio::time::timeout(timeout, report).await.map_err(|_| {
+            anyhow!(
+                "service was not started within {} seconds",
+                timeout.as_secs()
+            )
+        })?
+    }
+
+    /// Returns how the runner exited, if it did.
+    pub(crate) fn exit_status(&mut self) -> io::Result<Option<ExitStatus>> {
+        self.child.try_wait()
+    }
+
+    /// Explains why the runner exited with `status`, pointing out the
+    /// resource limit which got it killed if there is one.
+    pub(crate) async fn describe_exit(&mut self, status: ExitStatus) -> String {
+        // The service says why it aborted right before it does
+        if let Some(stderr) = self.stderr.take() {
+            let _ = tokio::time::timeout(STDERR_WAIT, stderr).await;
+        }
+
+        describe_exit(
+            status,
+            &self.limits,
+            self.out_of_memory.load(Ordering::SeqCst),
+        )
+    }
+
+    /// Reads the resources the runner, and so its service, is using right
+    /// now from `/proc`.
+    pub(crate) fn usage(&self) -> io::Result<ResourceUsage> {
+        let pid = self
+            .child
+            .id()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "runner exited"))?;
+
+        read_usage(pid)
+    }
+
+    /// Asks the service to shut down gracefully. Kills the runner if the
+    /// service did not shut down after `drain_timeout`.
+    pub(crate) async fn stop(mut self, drain_timeout: Duration) -> anyhow::Result<()> {
+        drop(self.stdin.take());
+
+        let status = match tokio::time::timeout(drain_timeout, self.child.wait()).await {
+            Ok(status) => status?,
+            Err(_) => {
+                debug!("service did not drain in time, killing its runner");
+                self.child.kill().await?;
+
+                return Ok(());
+            }
+        };
+
+        if status.success() {
+            Ok(())
+        } else {
+            Err(anyhow!("service exited with {}", status))
+        }
+    }
+}
+
+impl Drop for Runner {
+    fn drop(&mut self) {
+        self.secrets.revoke(&self.id, &self.secret);
+
+        if let Some(cgroup) = self.cgroup.take() {
+            // Rather than once the child is dropped, so the cgroup empties out
+            let _ = self.child.start_kill();
+            tokio::spawn(remove_cgroup(cgroup));
+        }
+    }
+}
+
+/// Removes the cgroup of a runner once the runner is gone from it. Only
+/// cgroups without processes in them can be removed, and a runner which got
+/// killed takes a moment to exit.
+async fn remove_cgroup(cgroup: PathBuf) {
+    for _ in 0..CGROUP_REMOVE_TRIES {
+        match fs::remove_dir(&cgroup) {
+            Ok(()) => return,
+            Err(e) if e.raw_os_error() == Some(libc::EBUSY) => {
+                tokio::time::sleep(CGROUP_REMOVE_INTERVAL).await
+            }
+            Err(e) => {
+                warn!("failed to remove cgroup {:?}: {}", cgroup, e);
+                return;
+            }
+        }
+    }
+
+    warn!(
+        "cgroup {:?} still has processes in it, leaving it be",
+        cgroup
+    );
+}
+
+/// Creates a cgroup for a runner of deployment `id` in the cgroup of `config`,
+/// holding the processes in it to `cpu_percent` of a single core. The cgroup
+/// and its limit belong to the user of the api, so that a runner run as
+/// another user cannot lift the limit or leave the cgroup.
+fn create_cgroup(
+    config: &RunnerConfig,
+    id: DeploymentId,
+    cpu_percent: u64,
+) -> anyhow::Result<PathBuf> {
+    let parent = config
+        .cgroup
+        .as_ref()
+        .ok_or_else(|| anyhow!("cpu limits need a cgroup to hold runners to them"))?;
+
+    // A runner replacing a crashed one may start before the old one is gone
+    let cgroup = parent.join(format!("{}-{:08x}", id, rand::thread_rng().gen::<u32>()));
+    fs::create_dir(&cgroup).map_err(|e| anyhow!("failed to create cgroup {:?}: {}", cgroup, e))?;
+
+    // The kernel refuses quotas under a millisecond
+    let quota = cpu_percent.saturating_mul(CPU_PERIOD_US / 100).max(1000);
+    if let Err(e) = fs::write(
+        cgroup.join("cpu.max"),
+        format!("{} {}", quota, CPU_PERIOD_US),
+    ) {
+        let _ = fs::remove_dir(&cgroup);
+
+        return Err(anyhow!("failed to limit cpu of cgroup {:?}: {}", cgroup, e));
+    }
+
+    Ok(cgroup)
+}
+
+/// Passes on what a runner writes to stderr to the stderr of the api, looking
+/// out for the message the service aborts with when it fails to allocate.
+async fn watch_stderr(stderr: ChildStderr, out_of_memory: Arc<AtomicBool>) {
+    let mut lines = BufReader::new(stderr).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.starts_with("memory allocation of") && line.ends_with("failed") {
+            out_of_memory.store(true, Ordering::SeqCst);
+        }
+
+        eprintln!("{}", line);
+    }
+}
+
+/// Explains why a runner exited with `status`. Reaching the memory limit
+/// aborts the service, but so do plenty of other things, so it is only
+/// blamed when the service said it failed to allocate.
+fn describe_exit(status: ExitStatus, limits: &ResourceLimits, out_of_memory: bool) -> String {
+    match limits.memory_mb {
+        Some(memory_mb) if out_of_memory && status.signal() == Some(libc::SIGABRT) => format!(
+            "service reached its memory limit of {} MB and exited with {}",
+            memory_mb, status
+        ),
+        _ => format!("service exited with {}", status),
+    }
+}
+
+/// Reads the resources a process is using right now from `/proc`.
+fn read_usage(pid: u32) -> io::Result<ResourceUsage> {
+    let status = fs::read_to_string(format!("/proc/{}/status", pid))?;
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid))?;
+    let open_files = fs::read_dir(format!("/proc/{}/fd", pid))?.count() as u64;
+
+    // SAFETY: only reads a configuration value
+    let ticks_per_sec = unsafe { libc::sysconf(libc::_SC_CLK_TCK) }.max(1) as u64;
+
+    Ok(ResourceUsage {
+        memory_mb: memory_kb(&status) / 1024,
+        cpu_secs: cpu_ticks(&stat) / ticks_per_sec,
+        open_files,
+    })
+}
+
+/// The private memory of a process from its `/proc/<pid>/status`, which is
+/// what the `RLIMIT_DATA` its memory limit is enforced with counts.
+fn memory_kb(status: &str) -> u64 {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmData:"))
+        .and_then(|memory| {
+            memory
+                .trim()
+                .trim_end_matches("kB")
+                .trim()
+                .parse::<u64>()
+                .ok()
+        })
+        .unwrap_or_default()
+}
+
+/// The processor time a process took in user and system mode, in clock
+/// ticks, from its `/proc/<pid>/stat`.
+fn cpu_ticks(stat: &str) -> u64 {
+    // The name of the command comes before the fields, in parentheses
+    // which may contain anything
+    let fields: Vec<&str> = stat
+        .rsplit_once(')')
+        .map(|(_, fields)| fields.split_whitespace().collect())
+        .unwrap_or_default();
+
+    // User and system time, the 14th and 15th fields counting the pid and
+    // the command name
+    fields
+        .iter()
+        .skip(11)
+        .take(2)
+        .filter_map(|ticks| ticks.parse::<u64>().ok())
+        .sum()
+}
+
+/// Runners which are yet to report whether their service got started.
+#[derive(Clone, Default)]
+pub(crate) struct Starts(Arc<Mutex<HashMap<DeploymentId, oneshot::Sender<Started>>>>);
+
+impl Starts {
+    /// Gets ready for the runner of a deployment to report back. Has to be
+    /// called before the runner gets started so no report is missed.
+    pub(crate) async fn expect(&self, id: DeploymentId) -> oneshot::Receiver<Started> {
+        let (tx, rx) = oneshot::channel();
+        self.0.lock().await.insert(id, tx);
+
+        rx
+    }
+
+    pub(crate) async fn report(&self, id: &DeploymentId, started: Started) -> Result<(), Status> {
+        let tx = self.0.lock().await.remove(id).ok_or_else(|| {
+            Status::not_found(format!("no runner of deployment '{}' is starting", id))
+        })?;
+
+        // Only fails when the api stopped waiting
+        let _ = tx.send(started);
+
+        Ok(())
+    }
+}
+
+/// The secrets runners authenticate to the supervisor with. Every runner gets
+/// a fresh one when it is started, which lasts for as long as the runner.
+#[derive(Clone, Default)]
+pub(crate) struct Secrets(Arc<std::sync::Mutex<HashMap<DeploymentId, String>>>);
+
+impl Secrets {
+    /// Makes up the secret of the runner of a deployment, in place of the
+    /// one of any runner before it.
+    fn issue(&self, id: DeploymentId) -> String {
+        let secret: String = rand::thread_rng()
+            .sample_iter(&rand::distributions::Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect();
+
+        self.0
+            .lock()
+            .expect("runner secrets lock to not be poisoned")
+            .insert(id, secret.clone());
+
+        secret
+    }
+
+    /// Forgets the secret of a runner, unless it got replaced already.
+    fn revoke(&self, id: &DeploymentId, secret: &str) {
+        let mut secrets = self
+            .0
+            .lock()
+            .expect("runner secrets lock to not be poisoned");
+
+        if secrets.get(id).map(String::as_str) == Some(secret) {
+            secrets.remove(id);
+        }
+    }
+
+    /// Checks that a request comes from the current runner of a deployment.
+    fn check<T>(&self, request: &Request<T>, id: &DeploymentId) -> Result<(), Status> {
+        let secret = request
+            .metadata()
+            .get(SECRET_METADATA_KEY)
+            .and_then(|secret| secret.to_str().ok());
+        let secrets = self
+            .0
+            .lock()
+            .expect("runner secrets lock to not be poisoned");
+
+        match (secret, secrets.get(id)) {
+            (Some(secret), Some(expected)) if constant_time_eq(secret, expected) => Ok(()),
+            _ => Err(Status::unauthenticated(format!(
+                "not the runner of deployment '{}'",
+                id
+            ))),
+        }
+    }
+}
+
+/// Compares two secrets in a time that does not depend on where they differ.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Serves the runner processes of deployments with what their services need
+/// from the api. Every request has to come from the current runner of the
+/// deployment it is about, see [`Secrets`].
+pub(crate) struct RunnerSupervisor {
+    deployments: Arc<RwLock<Deployments>>,
+    persistence: Persistence,
+    provisioner_client: ProvisionerClient<Channel>,
+    run_logs_tx: UnboundedSender<Log>,
+    starts: Starts,
+    secrets: Secrets,
+}
+
+impl RunnerSupervisor {
+    pub(crate) fn new(
+        deployments: Arc<RwLock<Deployments>>,
+        persistence: Persistence,
+        provisioner_client: ProvisionerClient<Channel>,
+        run_logs_tx: UnboundedSender<Log>,
+        starts: Starts,
+        secrets: Secrets,
+    ) -> Self {
+        Self {
+            deployments,
+            persistence,
+            provisioner_client,
+            run_logs_tx,
+            starts,
+            secrets,
+        }
+    }
+
+    async fn deployment(&self, id: &DeploymentId) -> Result<Arc<Deployment>, Status> {
+        self.deployments
+            .read()
+            .await
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| Status::not_found(format!("could not find deployment for id '{}'", id)))
+    }
+}
+
+#[tonic::async_trait]
+impl Supervisor for RunnerSupervisor {
+    async fn report_started(
+        &self,
+        request: Request<StartedRequest>,
+    ) -> Result<Response<StartedResponse>, Status> {
+        let id = parse_id(&request.get_ref().deployment_id)?;
+        self.secrets.check(&request, &id)?;
+
+        let request = request.into_inner();
+
+        let started = if request.error.is_empty() {
+            Ok(request.routable)
+        } else {
+            Err(request.error)
+        };
+
+        self.starts.report(&id, started).await?;
+
+        Ok(Response::new(StartedResponse {}))
+    }
+
+    async fn provision(
+        &self,
+        request: Request<ProvisionRequest>,
+    ) -> Result<Response<ResourceResponse>, Status> {
+        let id = parse_id(&request.get_ref().deployment_id)?;
+        self.secrets.check(&request, &id)?;
+
+        let request = request.into_inner();
+        let deployment = self.deployment(&id).await?;
+        let mut resource = request
+            .resource
+            .ok_or_else(|| Status::invalid_argument("missing the resource to provision"))?;
+        let r#type: resource::Type = resource
+            .resource_type
+            .clone()
+            .ok_or_else(|| Status::invalid_argument("missing the type of resource to provision"))?
+            .try_into()?;
+        let requested = resource::Request::new(r#type, resource.name().map(str::to_string));
+
+        // Services only ever get the resources of their own project
+        resource.project_name = deployment.meta().await.project.to_string();
+
+        let response = self
+            .provisioner_client
+            .clone()
+            .provision(Request::new(resource))
+            .await?
+            .into_inner();
+
+        deployment
+            .add_resource(response.clone().into_resource(requested), &self.persistence)
+            .await;
+
+        Ok(Response::new(response))
+    }
+
+    async fn send_logs(
+        &self,
+        mut request: Request<Streaming<LogRequest>>,
+    ) -> Result<Response<LogsResponse>, Status> {
+        while let Some(log) = request.get_mut().message().await? {
+            let deployment_id = parse_id(&log.deployment_id)?;
+            self.secrets.check(&request, &deployment_id)?;
+
+            let log = Log {
+                deployment_id,
+                datetime: DateTime::parse_from_rfc3339(&log.datetime)
+                    .map_err(|e| Status::invalid_argument(e.to_string()))?
+                    .with_timezone(&Utc),
+                item: serde_json::from_str(&log.item)
+                    .map_err(|e| Status::invalid_argument(e.to_string()))?,
+            };
+
+            // Only fails when the api is shutting down
+            let _ = self.run_logs_tx.send(log);
+        }
+
+        Ok(Response::new(LogsResponse {}))
+    }
+}
+
+fn parse_id(id: &str) -> Result<DeploymentId, Status> {
+    DeploymentId::from_str(id)
+        .map_err(|e| Status::invalid_argument(format!("invalid deployment id '{}': {}", id, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::net::{Ipv4Addr, SocketAddr};
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+    use std::time::Duration;
+
+    use chrono::Utc;
+    use rocket::tokio;
+    use shuttle_common::{LogItem, ResourceLimits};
+    use shuttle_proto::provisioner::provisioner_client::ProvisionerClient;
+    use shuttle_proto::runtime::supervisor_client::SupervisorClient;
+    use shuttle_proto::runtime::supervisor_server::SupervisorServer;
+    use shuttle_proto::runtime::SECRET_METADATA_KEY;
+    use shuttle_proto::runtime::{LogRequest, ProvisionRequest, StartedRequest};
+    use shuttle_service::logger::Log;
+    use tokio::sync::mpsc::{self, UnboundedReceiver};
+    use tonic::transport::{Channel, Endpoint, Server};
+    use tonic::{Code, Request};
+    use uuid::Uuid;
+
+    use super::{
+        cpu_ticks, create_cgroup, describe_exit, memory_kb, read_usage, RunnerConfig,
+        RunnerSupervisor, Secrets, Starts,
+    };
+    use crate::persistence::Persistence;
+
+    fn with_secret<T>(message: T, secret: &str) -> Request<T> {
+        let mut request = Request::new(message);
+        request
+            .metadata_mut()
+            .insert(SECRET_METADATA_KEY, secret.parse().unwrap());
+
+        request
+    }
+
+    /// Serves a supervisor on a free port and connects to it.
+    async fn supervisor(
+        starts: Starts,
+        secrets: Secrets,
+    ) -> (SupervisorClient<Channel>, UnboundedReceiver<Log>) {
+        let (run_logs_tx, run_logs_rx) = mpsc::unbounded_channel();
+        let supervisor = RunnerSupervisor::new(
+            Default::default(),
+            Persistence::new_in_memory().await.unwrap(),
+            // Never reached since no request gets through to provisioning
+            ProvisionerClient::new(Endpoint::from_static("http://127.0.0.1:1").connect_lazy()),
+            run_logs_tx,
+            starts,
+            secrets,
+        );
+
+        let addr = std::net::TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        tokio::spawn(
+            Server::builder()
+                .add_service(SupervisorServer::new(supervisor))
+                .serve(addr),
+        );
+
+        for _ in 0..50 {
+            if let Ok(client) = SupervisorClient::connect(format!("http://{}", addr)).await {
+                return (client, run_logs_rx);
+            }
+
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        panic!("supervisor did not start");
+    }
+
+    #[tokio::test]
+    async fn supervisor_authenticates_runners() {
+        let starts = Starts::default();
+        let secrets = Secrets::default();
+        let (mut client, mut logs) = supervisor(starts.clone(), secrets.clone()).await;
+
+        let id = Uuid::new_v4();
+        let started = starts.expect(id).await;
+        let secret = secrets.issue(id);
+        let other_secret = secrets.issue(Uuid::new_v4());
+
+        let report = || StartedRequest {
+            deployment_id: id.to_string(),
+            routable: true,
+            error: String::new(),
+        };
+        let log = || LogRequest {
+            deployment_id: id.to_string(),
+            datetime: Utc::now().to_rfc3339(),
+            item: serde_json::to_string(&LogItem {
+                body: "hello".to_string(),
+                level: log::Level::Info,
+                target: "service".to_string(),
+                fields: BTreeMap::new(),
+                spans: Vec::new(),
+            })
+            .unwrap(),
+        };
+
+        for request in [
+            Request::new(report()),
+            with_secret(report(), "guessed"),
+            with_secret(report(), &other_secret),
+        ] {
+            let err = client.report_started(request).await.unwrap_err();
+            assert_eq!(err.code(), Code::Unauthenticated);
+        }
+
+        let err = client
+            .provision(with_secret(
+                ProvisionRequest {
+                    deployment_id: id.to_string(),
+                    resource: None,
+                },
+                &other_secret,
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+
+        let err = client
+            .send_logs(with_secret(futures::stream::iter([log()]), &other_secret))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+        assert!(logs.try_recv().is_err());
+
+        client
+            .report_started(with_secret(report(), &secret))
+            .await
+            .unwrap();
+        assert_eq!(started.await.unwrap(), Ok(true));
+
+        client
+            .send_logs(with_secret(futures::stream::iter([log()]), &secret))
+            .await
+            .unwrap();
+        assert_eq!(logs.recv().await.unwrap().item.body, "hello");
+    }
+
+    #[test]
+    fn secrets_of_replaced_runners() {
+        let secrets = Secrets::default();
+        let id = Uuid::new_v4();
+        let check = |secret: &str| secrets.check(&with_secret((), secret), &id).is_ok();
+
+        let first = secrets.issue(id);
+        assert!(check(&first));
+
+        let second = secrets.issue(id);
+        assert!(!check(&first));
+        assert!(check(&second));
+
+        // The runner which got replaced going away leaves the new one be
+        secrets.revoke(&id, &first);
+        assert!(check(&second));
+
+        secrets.revoke(&id, &second);
+        assert!(!check(&second));
+    }
+
+    #[test]
+    fn describe_exits() {
+        let limits = ResourceLimits {
+            memory_mb: Some(64),
+            cpu_percent: None,
+            open_files: None,
+        };
+        let failed = ExitStatus::from_raw(3 << 8);
+        let aborted = ExitStatus::from_raw(libc::SIGABRT);
+
+        assert_eq!(
+            describe_exit(failed, &limits, false),
+            "service exited with exit status: 3"
+        );
+        assert_eq!(
+            describe_exit(aborted, &limits, true),
+            format!(
+                "service reached its memory limit of 64 MB and exited with {}",
+                aborted
+            )
+        );
+        // Panicking while panicking aborts as well
+        assert_eq!(
+            describe_exit(aborted, &limits, false),
+            format!("service exited with {}", aborted)
+        );
+        assert_eq!(
+            describe_exit(aborted, &ResourceLimits::default(), true),
+            format!("service exited with {}", aborted)
+        );
+    }
+
+    #[test]
+    fn cgroups_hold_runners_to_cpu_limits() {
+        let parent = std::env::temp_dir().join(format!("shuttle-cgroup-{}", Uuid::new_v4()));
+        fs::create_dir_all(&parent).unwrap();
+        let mut config = RunnerConfig {
+            path: "shuttle-runner".into(),
+            cgroup: None,
+            uid: None,
+            gid: None,
+            supervisor_port: 8002,
+            provisioner_uri: "http://localhost:5001".to_string(),
+        };
+        let id = Uuid::new_v4();
+
+        assert!(create_cgroup(&config, id, 50).is_err());
+
+        config.cgroup = Some(parent.clone());
+        let half = create_cgroup(&config, id, 50).unwrap();
+        let least = create_cgroup(&config, id, 0).unwrap();
+
+        assert_ne!(half, least);
+        assert!(half.starts_with(&parent));
+        assert_eq!(
+            fs::read_to_string(half.join("cpu.max")).unwrap(),
+            "50000 100000"
+        );
+        assert_eq!(
+            fs::read_to_string(least.join("cpu.max")).unwrap(),
+            "1000 100000"
+        );
+
+        fs::remove_dir_all(parent).unwrap();
+    }
+
+    #[test]
+    fn parse_usage() {
+        let status =
+            "Name:\tservice\nVmPeak:\t  900000 kB\nVmRSS:\t    2048 kB\nVmData:\t   40960 kB\n";
+        let stat = "4242 (a (weird) name) S 1 4242 4242 0 -1 4194560 1000 0 0 0 250 50 0 0 20 0 9";
+
+        assert_eq!(memory_kb(status), 40960);
+        assert_eq!(cpu_ticks(stat), 300);
+        assert_eq!(memory_kb(""), 0);
+        assert_eq!(cpu_ticks(""), 0);
+    }
+
+    #[test]
+    fn usage_of_running_process() {
+        let usage = read_usage(std::process::id()).unwrap();
+
+        assert!(usage.memory_mb > 0);
+        assert!(usage.open_files > 0);
+    }
+
+    #[tokio::test]
+    async fn starts_are_reported_once() {
+        let starts = Starts::default();
+        let id = Uuid::new_v4();
+
+        let started = starts.expect(id).await;
+        starts.report(&id, Ok(true)).await.unwrap();
+
+        assert_eq!(started.await.unwrap(), Ok(true));
+
+        let err = starts.report(&id, Ok(true)).await.unwrap_err();
+        assert_eq!(err.code(), Code::NotFound);
+    }
+}
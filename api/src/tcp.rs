@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr};
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+use rocket::tokio;
+use shuttle_common::project::ProjectName;
+use shuttle_common::Port;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{watch, Mutex, RwLock};
+use tokio::task::JoinHandle;
+
+/// How long a connection is held on to while its port has nowhere to go
+/// yet, like when the first deployment of a project is still starting.
+const TARGET_WAIT: Duration = Duration::from_secs(30);
+
+/// Passes raw TCP connections through to services that speak something
+/// other than HTTP. Every such project gets a public port of its own, out
+/// of a range set aside for this, which it keeps across deployments.
+pub(crate) struct TcpProxy {
+    bind_addr: IpAddr,
+    ports: Option<RangeInclusive<Port>>,
+    allocated: Mutex<HashMap<ProjectName, Port>>,
+    forwards: RwLock<HashMap<Port, Forward>>,
+}
+
+/// A public port being listened on, along with the local port of the
+/// deployment its connections currently go to, or 0 if there is none yet.
+/// Connections wait for a port other than 0 for a while.
+struct Forward {
+    target: watch::Sender<Port>,
+    handle: JoinHandle<()>,
+}
+
+impl TcpProxy {
+    /// Creates a proxy handing out public ports from `ports`, or none at all
+    /// if there is no range. `allocated` are the ports projects already got.
+    pub(crate) fn new(
+        bind_addr: IpAddr,
+        ports: Option<RangeInclusive<Port>>,
+        allocated: HashMap<ProjectName, Port>,
+    ) -> Self {
+        Self {
+            bind_addr,
+            ports,
+            allocated: Mutex::new(allocated),
+            forwards: Default::default(),
+        }
+    }
+
+    /// Whether this instance passes TCP connections through at all.
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.ports.is_some()
+    }
+
+    /// Gets the public port of a project, handing out a free one if the
+    /// project does not have one yet. Returns `None` if all ports are taken.
+    pub(crate) async fn port_for_project(&self, project: &ProjectName) -> Option<Port> {
+        let mut allocated = self.allocated.lock().await;
+
+        if let Some(port) = allocated.get(project) {
+            return Some(*port);
+        }
+
+        let port = self
+            .ports
+            .clone()?
+            .find(|port| !allocated.values().any(|taken| taken == port))?;
+        allocated.insert(project.clone(), port);
+
+        Some(port)
+    }
+
+    /// Starts listening on a public port, unless that is happening already.
+    /// Connections only go anywhere once they are [`forward`](Self::forward)ed.
+    pub(crate) async fn listen(&self, public: Port) -> io::Result<()> {
+        let mut forwards = self.forwards.write().await;
+
+        if forwards.contains_key(&public) {
+            return Ok(());
+        }
+
+        let listener = TcpListener::bind((self.bind_addr, public)).await?;
+        let (target, receiver) = watch::channel(0);
+
+        debug!("passing TCP connections on port {} through", public);
+
+        let handle = tokio::spawn(accept(listener, receiver));
+        forwards.insert(public, Forward { target, handle });
+
+        Ok(())
+    }
+
+    /// Sends the connections coming in on the `public` port to the `local`
+    /// port of a deployment from now on.
+    pub(crate) async fn forward(&self, public: Port, local: Port) {
+        if let Some(forward) = self.forwards.read().await.get(&public) {
+            forward.target.send_replace(local);
+        }
+    }
+
+    /// Stops listening on a public port, leaving open connections be.
+    pub(crate) async fn stop(&self, public: Port) {
+        if let Some(forward) = self.forwards.write().await.remove(&public) {
+            forward.handle.abort();
+        }
+    }
+
+    /// Gives the public port of a deleted project back, so that it can be
+    /// handed out again, and stops listening on it.
+    pub(crate) async fn release(&self, project: &ProjectName) {
+        if let Some(public) = self.allocated.lock().await.remove(project) {
+            self.stop(public).await;
+        }
+    }
+}
+
+async fn accept(listener: TcpListener, target: watch::Receiver<Port>) {
+    loop {
+        let (mut inbound, remote_addr) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!("failed to accept TCP connection: {}", e);
+                continue;
+            }
+        };
+
+        let target = target.clone();
+
+        tokio::spawn(async move {
+            let port = match tokio::time::timeout(TARGET_WAIT, wait_for_target(target)).await {
+                Ok(Some(port)) => port,
+                _ => {
+                    warn!(
+                        "dropping TCP connection from {}: nothing is deployed to pass it to",
+                        remote_addr
+                    );
+                    return;
+                }
+            };
+
+            let mut outbound = match TcpStream::connect((Ipv4Addr::LOCALHOST, port)).await {
+                Ok(outbound) => outbound,
+                Err(e) => {
+                    warn!("failed to reach service on port {}: {}", port, e);
+                    return;
+                }
+            };
+
+            if let Err(e) = tokio::io::copy_bidirectional(&mut inbound, &mut outbound).await {
+                debug!("TCP connection from {} ended: {}", remote_addr, e);
+            }
+        });
+    }
+}
+
+/// Waits for a deployment to be forwarded to. Returns `None` if the port
+/// stops being listened on first.
+async fn wait_for_target(mut target: watch::Receiver<Port>) -> Option<Port> {
+    loop {
+        let port = *target.borrow_and_update();
+        if port != 0 {
+            return Some(port);
+        }
+
+        target.changed().await.ok()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    use rocket::tokio;
+    use shuttle_common::project::ProjectName;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::TcpProxy;
+
+    /// A service that echoes everything back.
+    async fn echo() -> u16 {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let (mut reader, mut writer) = stream.split();
+                    tokio::io::copy(&mut reader, &mut writer).await.ok();
+                });
+            }
+        });
+
+        port
+    }
+
+    async fn round_trip(port: u16) -> String {
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port))
+            .await
+            .unwrap();
+        stream.write_all(b"ping").await.unwrap();
+
+        let mut buf = [0; 4];
+        stream.read_exact(&mut buf).await.unwrap();
+
+        String::from_utf8(buf.to_vec()).unwrap()
+    }
+
+    fn free_port() -> u16 {
+        std::net::TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port()
+    }
+
+    #[tokio::test]
+    async fn listen_forward_stop() {
+        let public = free_port();
+        let proxy = TcpProxy::new(
+            Ipv4Addr::LOCALHOST.into(),
+            Some(public..=public),
+            Default::default(),
+        );
+        let project = ProjectName::from_str("game").unwrap();
+
+        assert_eq!(proxy.port_for_project(&project).await, Some(public));
+        proxy.listen(public).await.unwrap();
+
+        // Connections made before anything is deployed wait for it
+        let early = tokio::spawn(round_trip(public));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!early.is_finished());
+
+        proxy.forward(public, echo().await).await;
+        assert_eq!(early.await.unwrap(), "ping");
+
+        // Later deployments take over the connections
+        proxy.forward(public, echo().await).await;
+        assert_eq!(round_trip(public).await, "ping");
+
+        proxy.stop(public).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(TcpStream::connect((Ipv4Addr::LOCALHOST, public))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn release() {
+        let public = free_port();
+        let proxy = TcpProxy::new(
+            Ipv4Addr::LOCALHOST.into(),
+            Some(public..=public),
+            Default::default(),
+        );
+        let game = ProjectName::from_str("game").unwrap();
+        let chat = ProjectName::from_str("chat").unwrap();
+
+        assert_eq!(proxy.port_for_project(&game).await, Some(public));
+        proxy.listen(public).await.unwrap();
+        assert_eq!(proxy.port_for_project(&chat).await, None);
+
+        proxy.release(&game).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        assert_eq!(proxy.port_for_project(&chat).await, Some(public));
+        proxy.listen(public).await.unwrap();
+    }
+}
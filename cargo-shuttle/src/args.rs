@@ -5,8 +5,11 @@ use std::{
     path::PathBuf,
 };
 
+use chrono::{DateTime, Utc};
 use clap::Parser;
+use log::Level;
 use shuttle_common::project::ProjectName;
+use uuid::Uuid;
 
 #[derive(Parser)]
 #[clap(
@@ -53,9 +56,14 @@ pub enum Command {
     /// view the status of a shuttle project
     Status,
     /// view the logs of a shuttle project
-    Logs,
+    Logs(LogsArgs),
     /// delete the latest deployment for a shuttle project
     Delete,
+    /// manage the deployments of a shuttle project
+    #[clap(subcommand)]
+    Deployment(DeploymentCommand),
+    /// roll a shuttle project back to the build of an earlier deployment
+    Rollback(RollbackArgs),
     /// create user credentials for the shuttle platform
     Auth(AuthArgs),
     /// login to the shuttle platform
@@ -64,6 +72,48 @@ pub enum Command {
     Run(RunArgs),
 }
 
+#[derive(Parser)]
+pub enum DeploymentCommand {
+    /// list the deployment history of a shuttle project, latest first
+    List(DeploymentListArgs),
+}
+
+#[derive(Parser)]
+pub struct DeploymentListArgs {
+    /// page of the history to show, starting at 0
+    #[clap(long, default_value = "0")]
+    pub page: u32,
+    /// number of deployments to show per page
+    #[clap(long, default_value = "10")]
+    pub limit: u32,
+}
+
+#[derive(Parser)]
+pub struct LogsArgs {
+    /// keep streaming new logs as they come in
+    #[clap(short, long)]
+    pub follow: bool,
+    /// only show logs of this level and more severe ones
+    #[clap(long)]
+    pub level: Option<Level>,
+    /// only show logs whose target starts with this, e.g. a module path
+    #[clap(long)]
+    pub target: Option<String>,
+    /// only show logs from this RFC 3339 timestamp on
+    #[clap(long)]
+    pub since: Option<DateTime<Utc>>,
+    /// only show logs up to this RFC 3339 timestamp
+    #[clap(long)]
+    pub until: Option<DateTime<Utc>>,
+}
+
+#[derive(Parser)]
+pub struct RollbackArgs {
+    /// id of the deployment to roll back to
+    #[clap()]
+    pub id: Uuid,
+}
+
 #[derive(Parser)]
 pub struct LoginArgs {
     /// api key for the shuttle platform
@@ -98,20 +148,32 @@ pub struct RunArgs {
 #[derive(Parser, Debug)]
 pub struct InitArgs {
     /// Initialize with axum framework
-    #[clap(long, conflicts_with_all = &["rocket", "tide", "tower", "poem"])]
+    #[clap(long, conflicts_with_all = &["rocket", "tide", "tower", "poem", "actix_web", "warp", "salvo", "tonic"])]
     pub axum: bool,
-    /// Initialize with actix-web framework
-    #[clap(long, conflicts_with_all = &["axum", "tide", "tower", "poem"])]
+    /// Initialize with rocket framework
+    #[clap(long, conflicts_with_all = &["axum", "tide", "tower", "poem", "actix_web", "warp", "salvo", "tonic"])]
     pub rocket: bool,
     /// Initialize with tide framework
-    #[clap(long, conflicts_with_all = &["axum", "rocket", "tower", "poem"])]
+    #[clap(long, conflicts_with_all = &["axum", "rocket", "tower", "poem", "actix_web", "warp", "salvo", "tonic"])]
     pub tide: bool,
     /// Initialize with tower framework
-    #[clap(long, conflicts_with_all = &["axum", "rocket", "tide", "poem"])]
+    #[clap(long, conflicts_with_all = &["axum", "rocket", "tide", "poem", "actix_web", "warp", "salvo", "tonic"])]
     pub tower: bool,
     /// Initialize with poem framework
-    #[clap(long, conflicts_with_all = &["axum", "rocket", "tide", "tower"])]
+    #[clap(long, conflicts_with_all = &["axum", "rocket", "tide", "tower", "actix_web", "warp", "salvo", "tonic"])]
     pub poem: bool,
+    /// Initialize with actix-web framework
+    #[clap(long, conflicts_with_all = &["axum", "rocket", "tide", "tower", "poem", "warp", "salvo", "tonic"])]
+    pub actix_web: bool,
+    /// Initialize with warp framework
+    #[clap(long, conflicts_with_all = &["axum", "rocket", "tide", "tower", "poem", "actix_web", "salvo", "tonic"])]
+    pub warp: bool,
+    /// Initialize with salvo framework
+    #[clap(long, conflicts_with_all = &["axum", "rocket", "tide", "tower", "poem", "actix_web", "warp", "tonic"])]
+    pub salvo: bool,
+    /// Initialize with tonic framework
+    #[clap(long, conflicts_with_all = &["axum", "rocket", "tide", "tower", "poem", "actix_web", "warp", "salvo"])]
+    pub tonic: bool,
     /// Path to initialize a new shuttle project
     #[clap(
         parse(try_from_os_str = parse_init_path),
@@ -2,19 +2,26 @@ use std::collections::HashMap;
 use std::fmt::Write;
 use std::fs::File;
 use std::io::Read;
-use std::time::Duration;
 
 use anyhow::{anyhow, Context, Result};
+use log::LevelFilter;
 use reqwest::{Response, StatusCode};
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
 use reqwest_retry::policies::ExponentialBackoff;
 use reqwest_retry::RetryTransientMiddleware;
+use serde::de::DeserializeOwned;
 use shuttle_common::project::ProjectName;
-use shuttle_common::{ApiKey, ApiUrl, DeploymentMeta, DeploymentStateMeta, SHUTTLE_PROJECT_HEADER};
-use tokio::time::sleep;
+use shuttle_common::{
+    ApiKey, ApiUrl, BuildEvent, DeploymentId, DeploymentMeta, DeploymentStateMeta,
+    DeploymentSummary, LogFilter, Protocol, RuntimeLog, SHUTTLE_PROJECT_HEADER,
+};
+use url::form_urlencoded;
 
 use crate::print;
 
+/// Number of runtime logs to get from the api at once
+const LOG_PAGE_LIMIT: u32 = 1000;
+
 pub(crate) async fn auth(mut api_url: ApiUrl, username: String) -> Result<ApiKey> {
     let client = get_retry_client();
 
@@ -55,7 +62,7 @@ pub(crate) async fn delete(
         .await
         .context("failed to delete deployment on the Shuttle server")?;
 
-    let deployment_meta = to_api_result(res).await?;
+    let deployment_meta: DeploymentMeta = to_api_result(res).await?;
 
     println!("{}", deployment_meta);
 
@@ -72,6 +79,41 @@ pub(crate) async fn status(api_url: ApiUrl, api_key: &ApiKey, project: &ProjectN
     Ok(())
 }
 
+pub(crate) async fn deployments(
+    mut api_url: ApiUrl,
+    api_key: &ApiKey,
+    project: &ProjectName,
+    page: u32,
+    limit: u32,
+) -> Result<()> {
+    let client = get_retry_client();
+
+    let _ = write!(
+        api_url,
+        "/projects/{}/deployments?page={}&limit={}",
+        project, page, limit
+    );
+
+    let res: Response = client
+        .get(api_url)
+        .basic_auth(api_key.clone(), Some(""))
+        .send()
+        .await
+        .context("failed to get deployment history")?;
+
+    let deployments: Vec<DeploymentSummary> = to_api_result(res).await?;
+
+    if deployments.is_empty() {
+        println!("No deployments found for project '{}'", project);
+    }
+
+    for deployment in deployments {
+        println!("{}", deployment);
+    }
+
+    Ok(())
+}
+
 pub(crate) async fn shuttle_version(mut api_url: ApiUrl) -> Result<String> {
     let client = get_retry_client();
     api_url.push_str("/version");
@@ -95,13 +137,139 @@ pub(crate) async fn shuttle_version(mut api_url: ApiUrl) -> Result<String> {
     }
 }
 
-pub(crate) async fn logs(api_url: ApiUrl, api_key: &ApiKey, project: &ProjectName) -> Result<()> {
+pub(crate) async fn logs(
+    mut api_url: ApiUrl,
+    api_key: &ApiKey,
+    project: &ProjectName,
+    follow: bool,
+    filter: &LogFilter,
+) -> Result<()> {
+    if follow {
+        return follow_logs(api_url, api_key, project, filter).await;
+    }
+
     let client = get_retry_client();
 
-    let deployment_meta = get_deployment_meta(api_url, api_key, project, &client).await?;
+    let _ = write!(
+        api_url,
+        "/projects/{}/logs?limit={}&{}",
+        project,
+        LOG_PAGE_LIMIT,
+        log_filter_query(filter)
+    );
+
+    let mut after = None;
+
+    loop {
+        let mut url = api_url.clone();
+        if let Some(after) = after {
+            let _ = write!(url, "&after={}", after);
+        }
+
+        let res: Response = client
+            .get(url)
+            .basic_auth(api_key.clone(), Some(""))
+            .send()
+            .await
+            .context("failed to get logs")?;
+
+        let logs: Vec<RuntimeLog> = to_api_result(res).await?;
+        let last_page = logs.len() < LOG_PAGE_LIMIT as usize;
+
+        for log in logs {
+            after = Some(log.id);
+            print::log(log.datetime, log.item);
+        }
+
+        if last_page {
+            return Ok(());
+        }
+    }
+}
+
+/// Turns a log filter into the query string the api expects.
+fn log_filter_query(filter: &LogFilter) -> String {
+    let mut query = form_urlencoded::Serializer::new(String::new());
+
+    if let Some(level) = filter.level {
+        query.append_pair("level", level.as_str());
+    }
+    if let Some(target) = &filter.target {
+        query.append_pair("target", target);
+    }
+    if let Some(since) = filter.since {
+        query.append_pair("since", &since.to_rfc3339());
+    }
+    if let Some(until) = filter.until {
+        query.append_pair("until", &until.to_rfc3339());
+    }
+
+    query.finish()
+}
+
+/// Prints the runtime logs of a project as the api streams them until the
+/// connection is closed.
+async fn follow_logs(
+    mut api_url: ApiUrl,
+    api_key: &ApiKey,
+    project: &ProjectName,
+    filter: &LogFilter,
+) -> Result<()> {
+    let _ = write!(
+        api_url,
+        "/projects/{}/logs/stream?{}",
+        project,
+        log_filter_query(filter)
+    );
+
+    let client = get_retry_client();
+
+    stream_events(api_url, api_key, &client, |log: RuntimeLog| {
+        print::log(log.datetime, log.item);
+
+        true
+    })
+    .await
+}
+
+/// Reads the server-sent events of an api stream, handing the data of each
+/// one to `on_event` until the stream ends or `on_event` returns `false`.
+async fn stream_events<T: DeserializeOwned>(
+    api_url: ApiUrl,
+    api_key: &ApiKey,
+    client: &ClientWithMiddleware,
+    mut on_event: impl FnMut(T) -> bool,
+) -> Result<()> {
+    let mut res: Response = client
+        .get(api_url)
+        .basic_auth(api_key.clone(), Some(""))
+        .send()
+        .await
+        .context("failed to connect to the stream")?;
+
+    if res.status() != StatusCode::OK {
+        return Err(anyhow!("{}", res.text().await?));
+    }
+
+    let mut buf = Vec::new();
+
+    while let Some(chunk) = res.chunk().await.context("stream was interrupted")? {
+        buf.extend_from_slice(&chunk);
+
+        // Every event ends with an empty line
+        while let Some(end) = buf.windows(2).position(|window| window == b"\n\n") {
+            let event: Vec<u8> = buf.drain(..end + 2).collect();
+            let event = String::from_utf8_lossy(&event);
 
-    for (datetime, log_item) in deployment_meta.runtime_logs {
-        print::log(datetime, log_item);
+            for data in event.lines().filter_map(|line| line.strip_prefix("data:")) {
+                let data = serde_json::from_str(data.trim_start())
+                    .context("failed to parse streamed event")?;
+
+                if !on_event(data) {
+                    return Ok(());
+                }
+            }
+        }
     }
 
     Ok(())
@@ -137,10 +305,32 @@ pub(crate) async fn deploy(
     api_url: ApiUrl,
     api_key: &ApiKey,
     project: &ProjectName,
+    log_level: Option<LevelFilter>,
+    protocol: Option<Protocol>,
+    health_path: Option<&str>,
 ) -> Result<DeploymentStateMeta> {
     let mut url = api_url.clone();
     let _ = write!(url, "/projects/{}", project.as_str());
 
+    // The serializer is not `Send`, so it cannot be held across the awaits below
+    let query = {
+        let mut query = form_urlencoded::Serializer::new(String::new());
+        if let Some(log_level) = log_level {
+            query.append_pair("log_level", &log_level.to_string());
+        }
+        if let Some(protocol) = protocol {
+            query.append_pair("protocol", &protocol.to_string());
+        }
+        if let Some(health_path) = health_path {
+            query.append_pair("health_path", health_path);
+        }
+
+        query.finish()
+    };
+    if !query.is_empty() {
+        let _ = write!(url, "?{}", query);
+    }
+
     let client = get_retry_client();
 
     let mut package_file = package_file;
@@ -158,22 +348,85 @@ pub(crate) async fn deploy(
         .await
         .context("failed to send deployment to the Shuttle server")?;
 
-    let mut deployment_meta = to_api_result(res).await?;
+    let deployment_meta = to_api_result(res).await?;
 
-    let mut log_pos = 0;
+    wait_for_deployment(deployment_meta, api_url, api_key, project, &client).await
+}
 
-    while !matches!(
-        deployment_meta.state,
-        DeploymentStateMeta::Deployed | DeploymentStateMeta::Error(_)
-    ) {
-        print_log(&deployment_meta.build_logs, &mut log_pos);
+pub(crate) async fn rollback(
+    api_url: ApiUrl,
+    api_key: &ApiKey,
+    project: &ProjectName,
+    id: &DeploymentId,
+) -> Result<DeploymentStateMeta> {
+    let mut url = api_url.clone();
+    let _ = write!(url, "/projects/{}/deployments/{}/rollback", project, id);
 
-        sleep(Duration::from_millis(350)).await;
+    let client = get_retry_client();
 
-        deployment_meta = get_deployment_meta(api_url.clone(), api_key, project, &client).await?;
-    }
+    let res: Response = client
+        .post(url)
+        .basic_auth(api_key.clone(), Some(""))
+        .send()
+        .await
+        .context("failed to request rollback from the Shuttle server")?;
+
+    let deployment_meta = to_api_result(res).await?;
+
+    wait_for_deployment(deployment_meta, api_url, api_key, project, &client).await
+}
+
+/// Follows a new deployment until it is either deployed or failed, printing
+/// its build output and the stages it goes through along the way.
+async fn wait_for_deployment(
+    deployment_meta: DeploymentMeta,
+    api_url: ApiUrl,
+    api_key: &ApiKey,
+    project: &ProjectName,
+    client: &ClientWithMiddleware,
+) -> Result<DeploymentStateMeta> {
+    let mut url = api_url.clone();
+    let _ = write!(
+        url,
+        "/projects/{}/deployments/{}/build/stream",
+        project, deployment_meta.id
+    );
+
+    stream_events(url, api_key, client, |event: BuildEvent| match event {
+        BuildEvent::Line(line) => {
+            println!("{}", line);
 
-    print_log(&deployment_meta.build_logs, &mut log_pos);
+            true
+        }
+        BuildEvent::State(state) => {
+            print::state(&state);
+
+            !matches!(
+                state,
+                DeploymentStateMeta::Deployed
+                    | DeploymentStateMeta::Error(_)
+                    | DeploymentStateMeta::Deleted
+            )
+        }
+    })
+    .await
+    .context("failed to follow the build")?;
+
+    let mut url = api_url;
+    let _ = write!(
+        url,
+        "/projects/{}/deployments/{}",
+        project, deployment_meta.id
+    );
+
+    let res: Response = client
+        .get(url)
+        .basic_auth(api_key.clone(), Some(""))
+        .send()
+        .await
+        .context("failed to get deployment metadata")?;
+
+    let deployment_meta: DeploymentMeta = to_api_result(res).await?;
 
     println!("{}", &deployment_meta);
 
@@ -205,20 +458,9 @@ pub(crate) async fn secrets(
         .map(|_| ())
 }
 
-fn print_log(logs: &Option<String>, log_pos: &mut usize) {
-    if let Some(logs) = logs {
-        let new = &logs[*log_pos..];
-
-        if !new.is_empty() {
-            *log_pos = logs.len();
-            print!("{}", new);
-        }
-    }
-}
-
-async fn to_api_result(res: Response) -> Result<DeploymentMeta> {
+async fn to_api_result<T: DeserializeOwned>(res: Response) -> Result<T> {
     let text = res.text().await?;
-    match serde_json::from_str::<DeploymentMeta>(&text) {
+    match serde_json::from_str::<T>(&text) {
         Ok(meta) => Ok(meta),
         Err(_) => Err(anyhow!("{}", text)),
     }
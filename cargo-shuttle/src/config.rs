@@ -5,9 +5,10 @@ use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Context, Result};
 use cargo_metadata::MetadataCommand;
+use log::LevelFilter;
 use serde::{Deserialize, Serialize};
 use shuttle_common::project::ProjectName;
-use shuttle_common::{ApiKey, ApiUrl, API_URL_DEFAULT};
+use shuttle_common::{ApiKey, ApiUrl, Protocol, API_URL_DEFAULT};
 
 use crate::args::ProjectArgs;
 
@@ -143,6 +144,13 @@ impl GlobalConfig {
 #[derive(Deserialize, Serialize, Default)]
 pub struct ProjectConfig {
     pub name: Option<ProjectName>,
+    /// The most verbose level of runtime logs the deployed service keeps
+    pub log_level: Option<LevelFilter>,
+    /// How clients reach the deployed service, over HTTP unless set
+    pub protocol: Option<Protocol>,
+    /// Path the deployed service answers with a 2xx status on once it is
+    /// ready to take requests
+    pub health_path: Option<String>,
 }
 
 pub type SecretsConfig = HashMap<String, String>;
@@ -401,6 +409,36 @@ impl RequestContext {
             .unwrap()
     }
 
+    /// Get the log level the project is configured with, if any.
+    ///
+    /// # Panics
+    /// Panics if the project configuration has not been loaded.
+    pub fn log_level(&self) -> Option<LevelFilter> {
+        self.project.as_ref().unwrap().as_ref().unwrap().log_level
+    }
+
+    /// Get the protocol the project is configured with, if any.
+    ///
+    /// # Panics
+    /// Panics if the project configuration has not been loaded.
+    pub fn protocol(&self) -> Option<Protocol> {
+        self.project.as_ref().unwrap().as_ref().unwrap().protocol
+    }
+
+    /// Get the health path the project is configured with, if any.
+    ///
+    /// # Panics
+    /// Panics if the project configuration has not been loaded.
+    pub fn health_path(&self) -> Option<&str> {
+        self.project
+            .as_ref()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .health_path
+            .as_deref()
+    }
+
     pub fn secrets(&self) -> HashMap<String, String> {
         self.secrets
             .as_ref()
@@ -15,7 +15,7 @@ use crossterm::{
 };
 use futures::StreamExt;
 use portpicker::pick_unused_port;
-use shuttle_common::{database::AwsRdsEngine, project::ProjectName, DatabaseReadyInfo};
+use shuttle_common::{database::AwsRdsEngine, project::ProjectName, resource, DatabaseReadyInfo};
 use shuttle_service::{database::Type, error::CustomError, Factory};
 use std::{collections::HashMap, io::stdout, time::Duration};
 use tokio::time::sleep;
@@ -36,9 +36,48 @@ impl LocalFactory {
 
 #[async_trait]
 impl Factory for LocalFactory {
-    async fn get_sql_connection_string(
+    async fn get_resource(
         &mut self,
+        request: resource::Request,
+    ) -> Result<resource::Response, shuttle_service::Error> {
+        let name = request.name.as_deref();
+        if let Some(name) = name {
+            if !resource::is_valid_name(name) {
+                return Err(shuttle_service::Error::Custom(CustomError::msg(format!(
+                    "invalid resource name '{name}': names are lowercase letters and digits, starting with a letter"
+                ))));
+            }
+        }
+
+        let conn_str = match request.r#type {
+            resource::Type::Database(db_type) => {
+                self.get_sql_connection_string(db_type, name).await?
+            }
+            resource::Type::Redis => self.get_redis_connection_string(name).await?,
+            resource::Type::MongoDb => self.get_mongodb_connection_string(name).await?,
+        };
+
+        println!(
+            "{:>12} can be reached at {}\n",
+            format!("{} ready", request).bold().cyan(),
+            conn_str
+        );
+
+        Ok(resource::Response {
+            r#type: request.r#type,
+            name: request.name,
+            connection_string_private: conn_str.clone(),
+            connection_string_public: conn_str,
+            details: Default::default(),
+        })
+    }
+}
+
+impl LocalFactory {
+    async fn get_sql_connection_string(
+        &self,
         db_type: Type,
+        name: Option<&str>,
     ) -> Result<String, shuttle_service::Error> {
         trace!("getting sql string for project '{}'", self.project);
 
@@ -53,25 +92,105 @@ impl Factory for LocalFactory {
             env,
             is_ready_cmd,
         } = db_type_to_config(db_type);
-        let container_name = format!("shuttle_{}_{}", self.project, r#type);
+        let port = self
+            .start_container(&r#type, name, image, &port, env, is_ready_cmd)
+            .await?;
+
+        let db_info = DatabaseReadyInfo::new(
+            engine,
+            username,
+            password,
+            database_name,
+            port,
+            "localhost".to_string(),
+            "localhost".to_string(),
+        );
+
+        Ok(db_info.connection_string_private())
+    }
+
+    async fn get_redis_connection_string(
+        &self,
+        name: Option<&str>,
+    ) -> Result<String, shuttle_service::Error> {
+        trace!("getting redis string for project '{}'", self.project);
+
+        let port = self
+            .start_container(
+                "shared_redis",
+                name,
+                "redis:7".to_string(),
+                "6379/tcp",
+                None,
+                vec!["redis-cli".to_string(), "ping".to_string()],
+            )
+            .await?;
+
+        Ok(format!("redis://localhost:{port}"))
+    }
+
+    async fn get_mongodb_connection_string(
+        &self,
+        name: Option<&str>,
+    ) -> Result<String, shuttle_service::Error> {
+        trace!("getting mongodb string for project '{}'", self.project);
+
+        let EngineConfig {
+            r#type,
+            image,
+            engine,
+            username,
+            password,
+            database_name,
+            port,
+            env,
+            is_ready_cmd,
+        } = mongodb_config();
+        let port = self
+            .start_container(&r#type, name, image, &port, env, is_ready_cmd)
+            .await?;
+
+        // The root user of the container is kept in the admin database
+        Ok(format!(
+            "{engine}://{username}:{password}@localhost:{port}/{database_name}?authSource=admin"
+        ))
+    }
+
+    /// Makes sure the container of a resource is running and ready, creating it if need be. Named
+    /// resources get a container of their own.
+    ///
+    /// Returns the port on the host the resource can be reached at.
+    async fn start_container(
+        &self,
+        r#type: &str,
+        name: Option<&str>,
+        image: String,
+        port: &str,
+        env: Option<Vec<String>>,
+        is_ready_cmd: Vec<String>,
+    ) -> Result<String, shuttle_service::Error> {
+        let container_name = match name {
+            Some(name) => format!("shuttle_{}_{}_{}", self.project, r#type, name),
+            None => format!("shuttle_{}_{}", self.project, r#type),
+        };
 
         let container = match self.docker.inspect_container(&container_name, None).await {
             Ok(container) => {
-                trace!("found DB container {container_name}");
+                trace!("found container {container_name}");
                 container
             }
             Err(bollard::errors::Error::DockerResponseServerError { status_code, .. })
                 if status_code == 404 =>
             {
                 self.pull_image(&image).await.expect("failed to pull image");
-                trace!("will create DB container {container_name}");
+                trace!("will create container {container_name}");
                 let options = Some(CreateContainerOptions {
                     name: container_name.clone(),
                 });
                 let mut port_bindings = HashMap::new();
                 let host_port = pick_unused_port().expect("system to have a free port");
                 port_bindings.insert(
-                    port.clone(),
+                    port.to_string(),
                     Some(vec![PortBinding {
                         host_port: Some(host_port.to_string()),
                         ..Default::default()
@@ -110,7 +229,7 @@ impl Factory for LocalFactory {
             .expect("container to have host config")
             .port_bindings
             .expect("port bindings on container")
-            .get(&port)
+            .get(port)
             .expect("a port bindings entry")
             .as_ref()
             .expect("a port bindings")
@@ -127,7 +246,7 @@ impl Factory for LocalFactory {
             .running
             .expect("state to have a running key")
         {
-            trace!("DB container '{container_name}' not running, so starting it");
+            trace!("container '{container_name}' not running, so starting it");
             self.docker
                 .start_container(&container_name, None::<StartContainerOptions<String>>)
                 .await
@@ -136,29 +255,9 @@ impl Factory for LocalFactory {
 
         self.wait_for_ready(&container_name, is_ready_cmd).await?;
 
-        let db_info = DatabaseReadyInfo::new(
-            engine,
-            username,
-            password,
-            database_name,
-            port,
-            "localhost".to_string(),
-            "localhost".to_string(),
-        );
-
-        let conn_str = db_info.connection_string_private();
-
-        println!(
-            "{:>12} can be reached at {}\n",
-            "DB ready".bold().cyan(),
-            conn_str
-        );
-
-        Ok(conn_str)
+        Ok(port)
     }
-}
 
-impl LocalFactory {
     async fn wait_for_ready(
         &self,
         container_name: &str,
@@ -359,3 +458,24 @@ fn db_type_to_config(db_type: Type) -> EngineConfig {
         },
     }
 }
+
+fn mongodb_config() -> EngineConfig {
+    EngineConfig {
+        r#type: "shared_mongodb".to_string(),
+        image: "mongo:5.0.10".to_string(),
+        engine: "mongodb".to_string(),
+        username: "mongodb".to_string(),
+        password: "password".to_string(),
+        database_name: "mongodb".to_string(),
+        port: "27017/tcp".to_string(),
+        env: Some(vec![
+            "MONGO_INITDB_ROOT_USERNAME=mongodb".to_string(),
+            "MONGO_INITDB_ROOT_PASSWORD=password".to_string(),
+        ]),
+        is_ready_cmd: vec![
+            "/bin/sh".to_string(),
+            "-c".to_string(),
+            "mongosh --quiet --eval 'db.runCommand({ ping: 1 }).ok' | grep 1".to_string(),
+        ],
+    }
+}
@@ -21,6 +21,53 @@ pub trait ShuttleInit {
     fn get_boilerplate_code_for_framework(&self) -> &'static str;
 }
 
+pub struct ShuttleInitActixWeb;
+
+impl ShuttleInit for ShuttleInitActixWeb {
+    fn set_cargo_dependencies(
+        &self,
+        dependencies: &mut Table,
+        manifest_path: &Path,
+        url: &Url,
+        get_dependency_version_fn: GetDependencyVersionFn,
+    ) {
+        set_key_value_dependency_version(
+            "actix-web",
+            dependencies,
+            manifest_path,
+            url,
+            get_dependency_version_fn,
+        );
+
+        set_inline_table_dependency_features(
+            "shuttle-service",
+            dependencies,
+            vec!["web-actix-web".to_string()],
+        );
+    }
+
+    fn get_boilerplate_code_for_framework(&self) -> &'static str {
+        indoc! {r#"
+        use actix_web::{get, web::ServiceConfig};
+
+        #[get("/hello")]
+        async fn hello_world() -> &'static str {
+            "Hello, world!"
+        }
+
+        #[shuttle_service::main]
+        async fn actix_web(
+        ) -> shuttle_service::ShuttleActixWeb<impl FnOnce(&mut ServiceConfig) + Sync + Send + Clone + 'static>
+        {
+            let config = move |cfg: &mut ServiceConfig| {
+                cfg.service(hello_world);
+            };
+
+            Ok(config.into())
+        }"#}
+    }
+}
+
 pub struct ShuttleInitAxum;
 
 impl ShuttleInit for ShuttleInitAxum {
@@ -274,6 +321,134 @@ impl ShuttleInit for ShuttleInitTower {
     }
 }
 
+pub struct ShuttleInitWarp;
+
+impl ShuttleInit for ShuttleInitWarp {
+    fn set_cargo_dependencies(
+        &self,
+        dependencies: &mut Table,
+        manifest_path: &Path,
+        url: &Url,
+        get_dependency_version_fn: GetDependencyVersionFn,
+    ) {
+        set_inline_table_dependency_features(
+            "shuttle-service",
+            dependencies,
+            vec!["web-warp".to_string()],
+        );
+
+        set_key_value_dependency_version(
+            "warp",
+            dependencies,
+            manifest_path,
+            url,
+            get_dependency_version_fn,
+        );
+    }
+
+    fn get_boilerplate_code_for_framework(&self) -> &'static str {
+        indoc! {r#"
+        use warp::{Filter, Reply};
+
+        #[shuttle_service::main]
+        async fn warp() -> shuttle_service::ShuttleWarp<(impl Reply,)> {
+            let route = warp::path("hello").map(|| "Hello, world!");
+
+            Ok(route.boxed())
+        }"#}
+    }
+}
+
+pub struct ShuttleInitSalvo;
+
+impl ShuttleInit for ShuttleInitSalvo {
+    fn set_cargo_dependencies(
+        &self,
+        dependencies: &mut Table,
+        manifest_path: &Path,
+        url: &Url,
+        get_dependency_version_fn: GetDependencyVersionFn,
+    ) {
+        set_inline_table_dependency_features(
+            "shuttle-service",
+            dependencies,
+            vec!["web-salvo".to_string()],
+        );
+
+        set_key_value_dependency_version(
+            "salvo",
+            dependencies,
+            manifest_path,
+            url,
+            get_dependency_version_fn,
+        );
+    }
+
+    fn get_boilerplate_code_for_framework(&self) -> &'static str {
+        indoc! {r#"
+        use salvo::prelude::*;
+
+        #[fn_handler]
+        async fn hello_world(res: &mut Response) {
+            res.render(Text::Plain("Hello, world!"));
+        }
+
+        #[shuttle_service::main]
+        async fn salvo() -> shuttle_service::ShuttleSalvo {
+            let router = Router::with_path("hello").get(hello_world);
+
+            Ok(router)
+        }"#}
+    }
+}
+
+pub struct ShuttleInitTonic;
+
+impl ShuttleInit for ShuttleInitTonic {
+    fn set_cargo_dependencies(
+        &self,
+        dependencies: &mut Table,
+        manifest_path: &Path,
+        url: &Url,
+        get_dependency_version_fn: GetDependencyVersionFn,
+    ) {
+        set_inline_table_dependency_features(
+            "shuttle-service",
+            dependencies,
+            vec!["grpc-tonic".to_string()],
+        );
+
+        set_key_value_dependency_version(
+            "tonic",
+            dependencies,
+            manifest_path,
+            url,
+            get_dependency_version_fn,
+        );
+
+        set_key_value_dependency_version(
+            "tonic-health",
+            dependencies,
+            manifest_path,
+            url,
+            get_dependency_version_fn,
+        );
+    }
+
+    fn get_boilerplate_code_for_framework(&self) -> &'static str {
+        indoc! {r#"
+        use tonic::transport::Server;
+
+        #[shuttle_service::main]
+        async fn tonic() -> shuttle_service::ShuttleTonic {
+            let (_, health_service) = tonic_health::server::health_reporter();
+            let router = Server::builder().add_service(health_service);
+
+            Ok(router.into())
+        }"#}
+    }
+}
+
 pub struct ShuttleInitNoOp;
 impl ShuttleInit for ShuttleInitNoOp {
     fn set_cargo_dependencies(
@@ -294,6 +469,10 @@ impl ShuttleInit for ShuttleInitNoOp {
 /// for writing framework-specific dependencies to `Cargo.toml` and generating
 /// boilerplate code in `src/lib.rs`.
 pub fn get_framework(init_args: &InitArgs) -> Box<dyn ShuttleInit> {
+    if init_args.actix_web {
+        return Box::new(ShuttleInitActixWeb);
+    }
+
     if init_args.axum {
         return Box::new(ShuttleInitAxum);
     }
@@ -314,6 +493,18 @@ pub fn get_framework(init_args: &InitArgs) -> Box<dyn ShuttleInit> {
         return Box::new(ShuttleInitPoem);
     }
 
+    if init_args.warp {
+        return Box::new(ShuttleInitWarp);
+    }
+
+    if init_args.salvo {
+        return Box::new(ShuttleInitSalvo);
+    }
+
+    if init_args.tonic {
+        return Box::new(ShuttleInitTonic);
+    }
+
     Box::new(ShuttleInitNoOp)
 }
 
@@ -453,6 +644,10 @@ mod shuttle_init_tests {
             tide: false,
             tower: false,
             poem: false,
+            actix_web: false,
+            warp: false,
+            salvo: false,
+            tonic: false,
             path: PathBuf::new(),
         };
 
@@ -462,6 +657,10 @@ mod shuttle_init_tests {
             "tide" => init_args.tide = true,
             "tower" => init_args.tower = true,
             "poem" => init_args.poem = true,
+            "actix-web" => init_args.actix_web = true,
+            "warp" => init_args.warp = true,
+            "salvo" => init_args.salvo = true,
+            "tonic" => init_args.tonic = true,
             _ => unreachable!(),
         }
 
@@ -486,13 +685,27 @@ mod shuttle_init_tests {
 
     #[test]
     fn test_get_framework_via_get_boilerplate_code() {
-        let frameworks = vec!["axum", "rocket", "tide", "tower", "poem"];
+        let frameworks = vec![
+            "axum",
+            "rocket",
+            "tide",
+            "tower",
+            "poem",
+            "actix-web",
+            "warp",
+            "salvo",
+            "tonic",
+        ];
         let framework_inits: Vec<Box<dyn ShuttleInit>> = vec![
             Box::new(ShuttleInitAxum),
             Box::new(ShuttleInitRocket),
             Box::new(ShuttleInitTide),
             Box::new(ShuttleInitTower),
             Box::new(ShuttleInitPoem),
+            Box::new(ShuttleInitActixWeb),
+            Box::new(ShuttleInitWarp),
+            Box::new(ShuttleInitSalvo),
+            Box::new(ShuttleInitTonic),
         ];
 
         for (framework, expected_framework_init) in frameworks.into_iter().zip(framework_inits) {
@@ -725,4 +938,129 @@ mod shuttle_init_tests {
 
         assert_eq!(cargo_toml.to_string(), expected);
     }
+
+    #[test]
+    fn test_set_cargo_dependencies_actix_web() {
+        let mut cargo_toml = cargo_toml_factory();
+        let dependencies = cargo_toml["dependencies"].as_table_mut().unwrap();
+        let manifest_path = PathBuf::new();
+        let url = Url::parse("https://shuttle.rs").unwrap();
+
+        set_inline_table_dependency_version(
+            "shuttle-service",
+            dependencies,
+            &manifest_path,
+            &url,
+            mock_get_latest_dependency_version,
+        );
+
+        ShuttleInitActixWeb.set_cargo_dependencies(
+            dependencies,
+            &manifest_path,
+            &url,
+            mock_get_latest_dependency_version,
+        );
+
+        let expected = indoc! {r#"
+            [dependencies]
+            shuttle-service = { version = "1.0", features = ["web-actix-web"] }
+            actix-web = "1.0"
+        "#};
+
+        assert_eq!(cargo_toml.to_string(), expected);
+    }
+
+    #[test]
+    fn test_set_cargo_dependencies_warp() {
+        let mut cargo_toml = cargo_toml_factory();
+        let dependencies = cargo_toml["dependencies"].as_table_mut().unwrap();
+        let manifest_path = PathBuf::new();
+        let url = Url::parse("https://shuttle.rs").unwrap();
+
+        set_inline_table_dependency_version(
+            "shuttle-service",
+            dependencies,
+            &manifest_path,
+            &url,
+            mock_get_latest_dependency_version,
+        );
+
+        ShuttleInitWarp.set_cargo_dependencies(
+            dependencies,
+            &manifest_path,
+            &url,
+            mock_get_latest_dependency_version,
+        );
+
+        let expected = indoc! {r#"
+            [dependencies]
+            shuttle-service = { version = "1.0", features = ["web-warp"] }
+            warp = "1.0"
+        "#};
+
+        assert_eq!(cargo_toml.to_string(), expected);
+    }
+
+    #[test]
+    fn test_set_cargo_dependencies_salvo() {
+        let mut cargo_toml = cargo_toml_factory();
+        let dependencies = cargo_toml["dependencies"].as_table_mut().unwrap();
+        let manifest_path = PathBuf::new();
+        let url = Url::parse("https://shuttle.rs").unwrap();
+
+        set_inline_table_dependency_version(
+            "shuttle-service",
+            dependencies,
+            &manifest_path,
+            &url,
+            mock_get_latest_dependency_version,
+        );
+
+        ShuttleInitSalvo.set_cargo_dependencies(
+            dependencies,
+            &manifest_path,
+            &url,
+            mock_get_latest_dependency_version,
+        );
+
+        let expected = indoc! {r#"
+            [dependencies]
+            shuttle-service = { version = "1.0", features = ["web-salvo"] }
+            salvo = "1.0"
+        "#};
+
+        assert_eq!(cargo_toml.to_string(), expected);
+    }
+
+    #[test]
+    fn test_set_cargo_dependencies_tonic() {
+        let mut cargo_toml = cargo_toml_factory();
+        let dependencies = cargo_toml["dependencies"].as_table_mut().unwrap();
+        let manifest_path = PathBuf::new();
+        let url = Url::parse("https://shuttle.rs").unwrap();
+
+        set_inline_table_dependency_version(
+            "shuttle-service",
+            dependencies,
+            &manifest_path,
+            &url,
+            mock_get_latest_dependency_version,
+        );
+
+        ShuttleInitTonic.set_cargo_dependencies(
+            dependencies,
+            &manifest_path,
+            &url,
+            mock_get_latest_dependency_version,
+        );
+
+        let expected = indoc! {r#"
+            [dependencies]
+            shuttle-service = { version = "1.0", features = ["grpc-tonic"] }
+            tonic = "1.0"
+            tonic-health = "1.0"
+        "#};
+
+        assert_eq!(cargo_toml.to_string(), expected);
+    }
 }
@@ -13,7 +13,10 @@ use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 use anyhow::{anyhow, Context, Result};
-pub use args::{Args, Command, DeployArgs, InitArgs, ProjectArgs, RunArgs};
+pub use args::{
+    Args, Command, DeployArgs, DeploymentCommand, DeploymentListArgs, InitArgs, LogsArgs,
+    ProjectArgs, RollbackArgs, RunArgs,
+};
 use args::{AuthArgs, LoginArgs};
 use cargo::core::compiler::CompileMode;
 use cargo::core::resolver::CliFeatures;
@@ -22,6 +25,7 @@ use cargo::ops::{CompileOptions, PackageOpts, Packages, TestOptions};
 use colored::Colorize;
 use config::RequestContext;
 use factory::LocalFactory;
+use log::LevelFilter;
 use semver::{Version, VersionReq};
 use shuttle_service::loader::{build_crate, Loader};
 use tokio::sync::mpsc;
@@ -31,7 +35,7 @@ use uuid::Uuid;
 #[macro_use]
 extern crate log;
 
-use shuttle_common::DeploymentStateMeta;
+use shuttle_common::{DeploymentStateMeta, LogFilter};
 
 pub struct Shuttle {
     ctx: RequestContext,
@@ -55,8 +59,10 @@ impl Shuttle {
             args.cmd,
             Command::Deploy(..)
                 | Command::Delete
+                | Command::Deployment(..)
+                | Command::Rollback(..)
                 | Command::Status
-                | Command::Logs
+                | Command::Logs(..)
                 | Command::Run(..)
         ) {
             self.load_project(&mut args.project_args)?;
@@ -71,8 +77,12 @@ impl Shuttle {
             }
             Command::Init(init_args) => self.init(init_args).await,
             Command::Status => self.status().await,
-            Command::Logs => self.logs().await,
+            Command::Logs(logs_args) => self.logs(logs_args).await,
             Command::Delete => self.delete().await,
+            Command::Deployment(DeploymentCommand::List(list_args)) => {
+                self.deployments_list(list_args).await
+            }
+            Command::Rollback(rollback_args) => return self.rollback(rollback_args).await,
             Command::Auth(auth_args) => self.auth(auth_args).await,
             Command::Login(login_args) => self.login(login_args).await,
             Command::Run(run_args) => self.local_run(run_args).await,
@@ -153,6 +163,34 @@ impl Shuttle {
         .context("failed to delete deployment")
     }
 
+    async fn deployments_list(&self, list_args: DeploymentListArgs) -> Result<()> {
+        client::deployments(
+            self.ctx.api_url(),
+            &self.ctx.api_key()?,
+            self.ctx.project_name(),
+            list_args.page,
+            list_args.limit,
+        )
+        .await
+        .context("failed to list deployments")
+    }
+
+    async fn rollback(&self, args: RollbackArgs) -> Result<CommandOutcome> {
+        let state_meta = client::rollback(
+            self.ctx.api_url(),
+            &self.ctx.api_key()?,
+            self.ctx.project_name(),
+            &args.id,
+        )
+        .await
+        .context("failed to roll back deployment")?;
+
+        Ok(match state_meta {
+            DeploymentStateMeta::Error(_) => CommandOutcome::DeploymentFailure,
+            _ => CommandOutcome::Ok,
+        })
+    }
+
     async fn status(&self) -> Result<()> {
         client::status(
             self.ctx.api_url(),
@@ -163,11 +201,18 @@ impl Shuttle {
         .context("failed to get status of deployment")
     }
 
-    async fn logs(&self) -> Result<()> {
+    async fn logs(&self, logs_args: LogsArgs) -> Result<()> {
         client::logs(
             self.ctx.api_url(),
             &self.ctx.api_key()?,
             self.ctx.project_name(),
+            logs_args.follow,
+            &LogFilter {
+                level: logs_args.level,
+                target: logs_args.target,
+                since: logs_args.since,
+                until: logs_args.until,
+            },
         )
         .await
         .context("failed to get logs of deployment")
@@ -200,7 +245,17 @@ impl Shuttle {
             self.ctx.project_name(),
             addr
         );
-        let (handle, so) = loader.load(&mut factory, addr, tx, deployment_id).await?;
+        let (handle, so, _) = loader
+            .load(
+                &mut factory,
+                addr,
+                // Ctrl-C stops the whole process, so there is never anything to drain
+                std::future::pending(),
+                tx,
+                deployment_id,
+                self.ctx.log_level().unwrap_or(LevelFilter::Info),
+            )
+            .await?;
 
         tokio::spawn(async move {
             while let Some(log) = rx.recv().await {
@@ -232,6 +287,9 @@ impl Shuttle {
             self.ctx.api_url(),
             &key,
             self.ctx.project_name(),
+            self.ctx.log_level(),
+            self.ctx.protocol(),
+            self.ctx.health_path(),
         )
         .await
         .context("failed to deploy cargo project")?;
@@ -1,21 +1,64 @@
+use std::collections::BTreeMap;
+
 use chrono::{DateTime, Local, Utc};
 use colored::{ColoredString, Colorize};
 use log::Level;
-use shuttle_common::LogItem;
+use shuttle_common::{DeploymentStateMeta, LogItem};
 
 pub fn log(datetime: DateTime<Utc>, log_item: LogItem) {
     let datetime: DateTime<Local> = DateTime::from(datetime);
+    let spans: String = log_item
+        .spans
+        .iter()
+        .map(|span| format!("{}{}:", span.name.bold(), format_span_fields(&span.fields)))
+        .collect();
+
     println!(
-        "{}{} {:<5} {}{} {}",
+        "{}{} {:<5} {}{} {}{}{}",
         "[".bright_black(),
         datetime.format("%Y-%m-%dT%H:%M:%SZ"),
         get_colored_level(&log_item.level),
         log_item.target,
         "]".bright_black(),
-        log_item.body
+        if spans.is_empty() { spans } else { spans + " " },
+        log_item.body,
+        format_fields(&log_item.fields)
     );
 }
 
+/// Renders the fields of an event as ` key=value` pairs.
+fn format_fields(fields: &BTreeMap<String, String>) -> String {
+    fields
+        .iter()
+        .map(|(key, value)| format!(" {}={}", key.italic(), value))
+        .collect()
+}
+
+/// Renders the fields of a span as `{key=value key=value}`, the way
+/// `tracing` does.
+fn format_span_fields(fields: &BTreeMap<String, String>) -> String {
+    if fields.is_empty() {
+        return String::new();
+    }
+
+    let fields: Vec<_> = fields
+        .iter()
+        .map(|(key, value)| format!("{}={}", key.italic(), value))
+        .collect();
+
+    format!("{{{}}}", fields.join(" "))
+}
+
+pub fn state(state: &DeploymentStateMeta) {
+    let label = match state {
+        DeploymentStateMeta::Error(_) => "Failed".bold().red(),
+        DeploymentStateMeta::Crashed(_) => "Crashed".bold().red(),
+        _ => "Stage".bold().green(),
+    };
+
+    println!("{:>12} {}", label, state);
+}
+
 fn get_colored_level(level: &Level) -> ColoredString {
     match level {
         Level::Trace => level.to_string().bright_black(),
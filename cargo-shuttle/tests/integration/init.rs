@@ -24,6 +24,10 @@ async fn cargo_shuttle_init(path: PathBuf) -> anyhow::Result<CommandOutcome> {
                 tide: false,
                 tower: false,
                 poem: false,
+                actix_web: false,
+                warp: false,
+                salvo: false,
+                tonic: false,
                 path,
             }),
         })
@@ -47,6 +51,10 @@ async fn cargo_shuttle_init_framework(path: PathBuf) -> anyhow::Result<CommandOu
                 tide: false,
                 tower: false,
                 poem: false,
+                actix_web: false,
+                warp: false,
+                salvo: false,
+                tonic: false,
                 path,
             }),
         })
@@ -244,3 +244,51 @@ async fn poem_postgres() {
 
     assert_eq!(request_text, "{\"id\":1,\"note\":\"Deploy to shuttle\"}");
 }
+
+#[tokio::test]
+async fn actix_web_hello_world() {
+    let port = cargo_shuttle_run("../examples/actix-web/hello-world").await;
+
+    let request_text = reqwest::Client::new()
+        .get(format!("http://localhost:{port}/hello"))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+
+    assert_eq!(request_text, "Hello, world!");
+}
+
+#[tokio::test]
+async fn warp_hello_world() {
+    let port = cargo_shuttle_run("../examples/warp/hello-world").await;
+
+    let request_text = reqwest::Client::new()
+        .get(format!("http://localhost:{port}/hello"))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+
+    assert_eq!(request_text, "Hello, world!");
+}
+
+#[tokio::test]
+async fn salvo_hello_world() {
+    let port = cargo_shuttle_run("../examples/salvo/hello-world").await;
+
+    let request_text = reqwest::Client::new()
+        .get(format!("http://localhost:{port}/hello"))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+
+    assert_eq!(request_text, "Hello, world!");
+}
@@ -0,0 +1,121 @@
+use proc_macro::TokenStream;
+use proc_macro_error::emit_error;
+use quote::{quote, ToTokens};
+use shuttle_common::cron::Schedule;
+use syn::{parse_macro_input, Ident, ItemFn, LitStr};
+
+pub(crate) fn r#impl(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let schedule = parse_macro_input!(attr as LitStr);
+    let fn_decl = parse_macro_input!(item as ItemFn);
+
+    let job = Job::from_item_fn(&fn_decl, schedule);
+
+    let expanded = quote! {
+        #fn_decl
+
+        #job
+    };
+
+    expanded.into()
+}
+
+struct Job {
+    fn_ident: Ident,
+    schedule: LitStr,
+}
+
+impl Job {
+    fn from_item_fn(item_fn: &ItemFn, schedule: LitStr) -> Self {
+        if item_fn.sig.asyncness.is_none() {
+            emit_error!(
+                item_fn.sig,
+                "shuttle_service::cron functions need to be async"
+            );
+        }
+
+        if !item_fn.sig.inputs.is_empty() {
+            emit_error!(
+                item_fn.sig.inputs,
+                "shuttle_service::cron functions cannot take arguments"
+            );
+        }
+
+        if let Err(e) = schedule.value().parse::<Schedule>() {
+            emit_error!(schedule, "{}", e);
+        }
+
+        Self {
+            fn_ident: item_fn.sig.ident.clone(),
+            schedule,
+        }
+    }
+}
+
+impl ToTokens for Job {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let fn_ident = &self.fn_ident;
+        let name = fn_ident.to_string();
+        let schedule = &self.schedule;
+
+        let job = quote! {
+            shuttle_service::inventory::submit! {
+                shuttle_service::cron::Job {
+                    name: #name,
+                    schedule: #schedule,
+                    run: || Box::pin(#fn_ident()),
+                }
+            }
+        };
+
+        job.to_tokens(tokens);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use quote::quote;
+    use syn::{parse_quote, Ident};
+
+    use super::Job;
+
+    #[test]
+    fn from_item_fn() {
+        let input = parse_quote!(
+            async fn cleanup() -> Result<(), shuttle_service::Error> {}
+        );
+
+        let actual = Job::from_item_fn(&input, parse_quote!("0 * * * *"));
+        let expected_ident: Ident = parse_quote!(cleanup);
+
+        assert_eq!(actual.fn_ident, expected_ident);
+        assert_eq!(actual.schedule.value(), "0 * * * *");
+    }
+
+    #[test]
+    fn output() {
+        let input = Job {
+            fn_ident: parse_quote!(cleanup),
+            schedule: parse_quote!("0 * * * *"),
+        };
+
+        let actual = quote!(#input);
+        let expected = quote! {
+            shuttle_service::inventory::submit! {
+                shuttle_service::cron::Job {
+                    name: "cleanup",
+                    schedule: "0 * * * *",
+                    run: || Box::pin(cleanup()),
+                }
+            }
+        };
+
+        assert_eq!(actual.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn ui() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/ui/cron/*.rs");
+    }
+}
@@ -1,3 +1,4 @@
+mod cron;
 mod main;
 
 use proc_macro::TokenStream;
@@ -8,3 +9,9 @@ use proc_macro_error::proc_macro_error;
 pub fn main(attr: TokenStream, item: TokenStream) -> TokenStream {
     main::r#impl(attr, item)
 }
+
+#[proc_macro_error]
+#[proc_macro_attribute]
+pub fn cron(attr: TokenStream, item: TokenStream) -> TokenStream {
+    cron::r#impl(attr, item)
+}
@@ -2,8 +2,8 @@ use proc_macro::TokenStream;
 use proc_macro_error::emit_error;
 use quote::{quote, ToTokens};
 use syn::{
-    parse_macro_input, parse_quote, spanned::Spanned, Attribute, FnArg, Ident, ItemFn, Pat, Path,
-    ReturnType, Signature, Stmt, Type,
+    parse_macro_input, parse_quote, spanned::Spanned, Attribute, FnArg, Ident, ItemFn, Lit, Meta,
+    NestedMeta, Pat, Path, ReturnType, Signature, Stmt, Type,
 };
 
 pub(crate) fn r#impl(_attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -17,9 +17,10 @@ pub(crate) fn r#impl(_attr: TokenStream, item: TokenStream) -> TokenStream {
         fn __binder(
             service: Box<dyn shuttle_service::Service>,
             addr: std::net::SocketAddr,
+            shutdown: shuttle_service::Shutdown,
             runtime: &shuttle_service::Runtime,
         ) -> shuttle_service::ServeHandle {
-            runtime.spawn(async move { service.bind(addr).await })
+            runtime.spawn(async move { service.bind_with_shutdown(addr, shutdown).await })
         }
 
         #fn_decl
@@ -38,6 +39,11 @@ pub(crate) fn r#impl(_attr: TokenStream, item: TokenStream) -> TokenStream {
             let boxed = Box::new(bootstrapper);
             Box::into_raw(boxed)
         }
+
+        #[no_mangle]
+        pub extern "C" fn _shuttle_abi_version() -> *const std::os::raw::c_char {
+            shuttle_service::ABI_VERSION.as_ptr() as *const std::os::raw::c_char
+        }
     };
 
     expanded.into()
@@ -53,8 +59,26 @@ struct Input {
     /// The identifier for a resource input
     ident: Ident,
 
-    /// The shuttle_service path to the builder for this resource
-    builder: Path,
+    /// The builder for this resource
+    builder: Builder,
+}
+
+#[derive(Debug, PartialEq)]
+struct Builder {
+    /// The shuttle_service path to the builder
+    path: Path,
+
+    /// Options to set on the builder, like the `name = "analytics"` in `#[shared::Postgres(name = "analytics")]`
+    options: Vec<BuilderOption>,
+}
+
+#[derive(Debug, PartialEq)]
+struct BuilderOption {
+    /// The builder method setting the option
+    key: Ident,
+
+    /// The value to set the option to
+    value: Lit,
 }
 
 impl Wrapper {
@@ -72,7 +96,7 @@ impl Wrapper {
                 _ => None,
             })
             .filter_map(|(pat_ident, attrs)| {
-                match attribute_to_path(attrs) {
+                match attribute_to_builder(attrs) {
                     Ok(builder) => Some(Input {
                         ident: pat_ident.ident.clone(),
                         builder,
@@ -114,21 +138,64 @@ fn check_return_type(signature: &Signature) {
     }
 }
 
-fn attribute_to_path(attrs: Vec<Attribute>) -> Result<Path, String> {
+fn attribute_to_builder(attrs: Vec<Attribute>) -> Result<Builder, String> {
     if attrs.is_empty() {
         return Err("resource needs an attribute configuration".to_string());
     }
 
-    let builder = attrs[0].path.clone();
+    let meta = attrs[0].parse_meta().map_err(|e| e.to_string())?;
 
-    Ok(builder)
+    let (path, nested) = match meta {
+        Meta::Path(path) => (path, Default::default()),
+        Meta::List(list) => (list.path, list.nested),
+        Meta::NameValue(_) => {
+            return Err(
+                "resource options go in parentheses, like `name = \"analytics\"`".to_string(),
+            )
+        }
+    };
+
+    let options = nested
+        .into_iter()
+        .map(|nested| match nested {
+            NestedMeta::Meta(Meta::NameValue(name_value)) => {
+                let key = name_value
+                    .path
+                    .get_ident()
+                    .cloned()
+                    .ok_or_else(|| "resource option needs a plain name".to_string())?;
+
+                Ok(BuilderOption {
+                    key,
+                    value: name_value.lit,
+                })
+            }
+            _ => Err("resource options need to look like `name = \"analytics\"`".to_string()),
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(Builder { path, options })
 }
 
 impl ToTokens for Wrapper {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
         let fn_ident = &self.fn_ident;
         let fn_inputs: Vec<_> = self.fn_inputs.iter().map(|i| i.ident.clone()).collect();
-        let fn_inputs_builder: Vec<_> = self.fn_inputs.iter().map(|i| i.builder.clone()).collect();
+        let fn_inputs_builder: Vec<_> = self
+            .fn_inputs
+            .iter()
+            .map(|i| i.builder.path.clone())
+            .collect();
+        let fn_inputs_builder_options: Vec<_> = self
+            .fn_inputs
+            .iter()
+            .map(|i| {
+                let keys: Vec<_> = i.builder.options.iter().map(|o| &o.key).collect();
+                let values: Vec<_> = i.builder.options.iter().map(|o| &o.value).collect();
+
+                quote!(#(.#keys(#values))*)
+            })
+            .collect();
 
         let factory_ident: Ident = if self.fn_inputs.is_empty() {
             parse_quote!(_factory)
@@ -148,14 +215,14 @@ impl ToTokens for Wrapper {
             async fn __shuttle_wrapper(
                 #factory_ident: &mut dyn shuttle_service::Factory,
                 runtime: &shuttle_service::Runtime,
-                logger: Box<dyn shuttle_service::log::Log>,
+                logger: shuttle_service::logger::Logger,
             ) -> Result<Box<dyn shuttle_service::Service>, shuttle_service::Error> {
                 #extra_imports
 
+                let cron_logger = logger.clone();
+
                 runtime.spawn_blocking(move || {
-                    shuttle_service::log::set_boxed_logger(logger)
-                        .map(|()| shuttle_service::log::set_max_level(shuttle_service::log::LevelFilter::Info))
-                        .expect("logger set should succeed");
+                    logger.install().expect("logger set should succeed");
                 })
                     .await
                     .map_err(|e| {
@@ -173,12 +240,15 @@ impl ToTokens for Wrapper {
                     })?;
 
 
-                #(let #fn_inputs = shuttle_service::#fn_inputs_builder::new().build(#factory_ident, runtime).await?;)*
+                #(let #fn_inputs = shuttle_service::#fn_inputs_builder::new()#fn_inputs_builder_options.build(#factory_ident, runtime).await?;)*
 
                 runtime.spawn(async {
                     #fn_ident(#(#fn_inputs),*)
                         .await
-                        .map(|ok| Box::new(ok) as Box<dyn shuttle_service::Service>)
+                        .map(|ok| {
+                            Box::new(shuttle_service::cron::Scheduled::new(Box::new(ok), cron_logger))
+                                as Box<dyn shuttle_service::Service>
+                        })
                 })
                     .await
                     .map_err(|e| {
@@ -207,7 +277,7 @@ mod tests {
     use quote::quote;
     use syn::{parse_quote, Ident};
 
-    use super::{Input, Wrapper};
+    use super::{Builder, BuilderOption, Input, Wrapper};
 
     #[test]
     fn from_with_return() {
@@ -234,12 +304,12 @@ mod tests {
             async fn __shuttle_wrapper(
                 _factory: &mut dyn shuttle_service::Factory,
                 runtime: &shuttle_service::Runtime,
-                logger: Box<dyn shuttle_service::log::Log>,
+                logger: shuttle_service::logger::Logger,
             ) -> Result<Box<dyn shuttle_service::Service>, shuttle_service::Error> {
+                let cron_logger = logger.clone();
+
                 runtime.spawn_blocking(move || {
-                    shuttle_service::log::set_boxed_logger(logger)
-                        .map(|()| shuttle_service::log::set_max_level(shuttle_service::log::LevelFilter::Info))
-                        .expect("logger set should succeed");
+                    logger.install().expect("logger set should succeed");
                 })
                 .await
                 .map_err(|e| {
@@ -259,7 +329,10 @@ mod tests {
                 runtime.spawn(async {
                     complex()
                         .await
-                        .map(|ok| Box::new(ok) as Box<dyn shuttle_service::Service>)
+                        .map(|ok| {
+                            Box::new(shuttle_service::cron::Scheduled::new(Box::new(ok), cron_logger))
+                                as Box<dyn shuttle_service::Service>
+                        })
                 })
                 .await
                 .map_err(|e| {
@@ -291,7 +364,10 @@ mod tests {
         let expected_ident: Ident = parse_quote!(complex);
         let expected_inputs: Vec<Input> = vec![Input {
             ident: parse_quote!(pool),
-            builder: parse_quote!(shared::Postgres),
+            builder: Builder {
+                path: parse_quote!(shared::Postgres),
+                options: Vec::new(),
+            },
         }];
 
         assert_eq!(actual.fn_ident, expected_ident);
@@ -309,6 +385,57 @@ mod tests {
         }
     }
 
+    #[test]
+    fn from_with_input_options() {
+        let mut input = parse_quote!(
+            async fn complex(
+                #[shared::Postgres] pool: PgPool,
+                #[shared::Postgres(name = "analytics")] analytics: PgPool,
+                #[shared::Postgres(max_connections = 20, acquire_timeout = "5s")] busy: PgPool,
+            ) -> ShuttleTide {
+            }
+        );
+
+        let actual = Wrapper::from_item_fn(&mut input);
+        let expected_inputs: Vec<Input> = vec![
+            Input {
+                ident: parse_quote!(pool),
+                builder: Builder {
+                    path: parse_quote!(shared::Postgres),
+                    options: Vec::new(),
+                },
+            },
+            Input {
+                ident: parse_quote!(analytics),
+                builder: Builder {
+                    path: parse_quote!(shared::Postgres),
+                    options: vec![BuilderOption {
+                        key: parse_quote!(name),
+                        value: parse_quote!("analytics"),
+                    }],
+                },
+            },
+            Input {
+                ident: parse_quote!(busy),
+                builder: Builder {
+                    path: parse_quote!(shared::Postgres),
+                    options: vec![
+                        BuilderOption {
+                            key: parse_quote!(max_connections),
+                            value: parse_quote!(20),
+                        },
+                        BuilderOption {
+                            key: parse_quote!(acquire_timeout),
+                            value: parse_quote!("5s"),
+                        },
+                    ],
+                },
+            },
+        ];
+
+        assert_eq!(actual.fn_inputs, expected_inputs);
+    }
+
     #[test]
     fn output_with_inputs() {
         let input = Wrapper {
@@ -316,11 +443,17 @@ mod tests {
             fn_inputs: vec![
                 Input {
                     ident: parse_quote!(pool),
-                    builder: parse_quote!(shared::Postgres),
+                    builder: Builder {
+                        path: parse_quote!(shared::Postgres),
+                        options: Vec::new(),
+                    },
                 },
                 Input {
                     ident: parse_quote!(redis),
-                    builder: parse_quote!(shared::Redis),
+                    builder: Builder {
+                        path: parse_quote!(shared::Redis),
+                        options: Vec::new(),
+                    },
                 },
             ],
         };
@@ -330,14 +463,14 @@ mod tests {
             async fn __shuttle_wrapper(
                 factory: &mut dyn shuttle_service::Factory,
                 runtime: &shuttle_service::Runtime,
-                logger: Box<dyn shuttle_service::log::Log>,
+                logger: shuttle_service::logger::Logger,
             ) -> Result<Box<dyn shuttle_service::Service>, shuttle_service::Error> {
                 use shuttle_service::ResourceBuilder;
 
+                let cron_logger = logger.clone();
+
                 runtime.spawn_blocking(move || {
-                    shuttle_service::log::set_boxed_logger(logger)
-                        .map(|()| shuttle_service::log::set_max_level(shuttle_service::log::LevelFilter::Info))
-                        .expect("logger set should succeed");
+                    logger.install().expect("logger set should succeed");
                 })
                 .await
                 .map_err(|e| {
@@ -360,7 +493,95 @@ mod tests {
                 runtime.spawn(async {
                     complex(pool, redis)
                         .await
-                        .map(|ok| Box::new(ok) as Box<dyn shuttle_service::Service>)
+                        .map(|ok| {
+                            Box::new(shuttle_service::cron::Scheduled::new(Box::new(ok), cron_logger))
+                                as Box<dyn shuttle_service::Service>
+                        })
+                })
+                .await
+                .map_err(|e| {
+                    if e.is_panic() {
+                        let mes = e
+                            .into_panic()
+                            .downcast_ref::<&str>()
+                            .map(|x| x.to_string())
+                            .unwrap_or_else(|| "<no panic message>".to_string());
+
+                        shuttle_service::Error::BuildPanic(mes)
+                    } else {
+                        shuttle_service::Error::Custom(shuttle_service::error::CustomError::new(e))
+                    }
+                })?
+            }
+        };
+
+        assert_eq!(actual.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn output_with_input_options() {
+        let input = Wrapper {
+            fn_ident: parse_quote!(complex),
+            fn_inputs: vec![
+                Input {
+                    ident: parse_quote!(pool),
+                    builder: Builder {
+                        path: parse_quote!(shared::Postgres),
+                        options: Vec::new(),
+                    },
+                },
+                Input {
+                    ident: parse_quote!(analytics),
+                    builder: Builder {
+                        path: parse_quote!(shared::Postgres),
+                        options: vec![BuilderOption {
+                            key: parse_quote!(name),
+                            value: parse_quote!("analytics"),
+                        }],
+                    },
+                },
+            ],
+        };
+
+        let actual = quote!(#input);
+        let expected = quote! {
+            async fn __shuttle_wrapper(
+                factory: &mut dyn shuttle_service::Factory,
+                runtime: &shuttle_service::Runtime,
+                logger: shuttle_service::logger::Logger,
+            ) -> Result<Box<dyn shuttle_service::Service>, shuttle_service::Error> {
+                use shuttle_service::ResourceBuilder;
+
+                let cron_logger = logger.clone();
+
+                runtime.spawn_blocking(move || {
+                    logger.install().expect("logger set should succeed");
+                })
+                .await
+                .map_err(|e| {
+                    if e.is_panic() {
+                        let mes = e
+                            .into_panic()
+                            .downcast_ref::<&str>()
+                            .map(|x| x.to_string())
+                            .unwrap_or_else(|| "<no panic message>".to_string());
+
+                        shuttle_service::Error::BuildPanic(mes)
+                    } else {
+                        shuttle_service::Error::Custom(shuttle_service::error::CustomError::new(e))
+                    }
+                })?;
+
+                let pool = shuttle_service::shared::Postgres::new().build(factory, runtime).await?;
+                let analytics = shuttle_service::shared::Postgres::new().name("analytics").build(factory, runtime).await?;
+
+                runtime.spawn(async {
+                    complex(pool, analytics)
+                        .await
+                        .map(|ok| {
+                            Box::new(shuttle_service::cron::Scheduled::new(Box::new(ok), cron_logger))
+                                as Box<dyn shuttle_service::Service>
+                        })
                 })
                 .await
                 .map_err(|e| {
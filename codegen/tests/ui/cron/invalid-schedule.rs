@@ -0,0 +1,2 @@
+#[shuttle_codegen::cron("0 24 * * *")]
+async fn cleanup() -> Result<(), shuttle_service::Error> {}
@@ -0,0 +1,262 @@
+//! Parsing of the schedules of cron jobs, shared by the `#[shuttle_service::cron]`
+//! macro to check them at compile time and by the service to run them.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc};
+
+// How far ahead to look for the next run before giving up on a schedule,
+// like one for the 30th of February
+const LOOKAHEAD_DAYS: i64 = 5 * 366;
+
+/// A schedule in the usual five fields of cron: minute, hour, day of the
+/// month, month and day of the week, all in UTC.
+///
+/// Every field is either `*` or a list of values and ranges like `1,5-10`,
+/// each of which can take a step like `*/15`. Sunday is both 0 and 7. As
+/// with cron, when both days are restricted either one of them has to match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduleError {
+    schedule: String,
+    reason: String,
+}
+
+impl Display for ScheduleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid cron schedule `{}`: {}",
+            self.schedule, self.reason
+        )
+    }
+}
+
+impl Error for ScheduleError {}
+
+impl FromStr for Schedule {
+    type Err = ScheduleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |reason: String| ScheduleError {
+            schedule: s.to_string(),
+            reason,
+        };
+
+        let fields: Vec<_> = s.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(error(format!("expected 5 fields, got {}", fields.len())));
+        }
+
+        let days_of_week = parse_field(fields[4], 0, 7).map_err(error)?;
+
+        Ok(Self {
+            minutes: parse_field(fields[0], 0, 59).map_err(error)?,
+            hours: parse_field(fields[1], 0, 23).map_err(error)?,
+            days_of_month: parse_field(fields[2], 1, 31).map_err(error)?,
+            months: parse_field(fields[3], 1, 12).map_err(error)?,
+            // Both 0 and 7 are Sunday
+            days_of_week: (days_of_week | days_of_week >> 7) & 0x7f,
+            // Like cron, a field starting with `*` does not restrict the day,
+            // even with a step
+            any_day_of_month: fields[2].starts_with('*'),
+            any_day_of_week: fields[4].starts_with('*'),
+        })
+    }
+}
+
+impl Schedule {
+    /// The first minute after `after` this schedule runs at, if there is one
+    /// in the next few years.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut time = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = after + Duration::days(LOOKAHEAD_DAYS);
+
+        while time < limit {
+            if !contains(self.months, time.month()) {
+                time = start_of_day(time.with_day(1)?) + Duration::days(32);
+                time = time.with_day(1)?;
+            } else if !self.day_matches(time) {
+                time = start_of_day(time) + Duration::days(1);
+            } else if !contains(self.hours, time.hour()) {
+                time = time.with_minute(0)? + Duration::hours(1);
+            } else if !contains(self.minutes, time.minute()) {
+                time = time + Duration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+
+        None
+    }
+
+    fn day_matches(&self, time: DateTime<Utc>) -> bool {
+        let day_of_month = contains(self.days_of_month, time.day());
+        let day_of_week = contains(self.days_of_week, time.weekday().num_days_from_sunday());
+
+        match (self.any_day_of_month, self.any_day_of_week) {
+            (false, false) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        }
+    }
+}
+
+fn start_of_day(time: DateTime<Utc>) -> DateTime<Utc> {
+    time.date().and_hms(0, 0, 0)
+}
+
+fn contains(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+/// Parses one field of a schedule into a set of its values, as bits.
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut set = 0;
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .map_err(|_| format!("invalid step in `{}`", part))?;
+                if step == 0 {
+                    return Err(format!("step of `{}` cannot be 0", part));
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let parse = |value: &str| -> Result<u32, String> {
+            value
+                .parse()
+                .ok()
+                .filter(|value| (min..=max).contains(value))
+                .ok_or_else(|| format!("`{}` is not between {} and {}", value, min, max))
+        };
+
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (parse(start)?, parse(end)?),
+            // A single value with a step runs to the end, like `5/15`
+            None if step > 1 => (parse(range)?, max),
+            None => (parse(range)?, parse(range)?),
+        };
+
+        if start > end {
+            return Err(format!("range `{}` is backwards", range));
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+
+    Ok(set)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Utc};
+
+    use super::Schedule;
+
+    fn at(datetime: &str) -> DateTime<Utc> {
+        datetime.parse().unwrap()
+    }
+
+    fn next(schedule: &str, after: &str) -> Option<DateTime<Utc>> {
+        schedule.parse::<Schedule>().unwrap().next_after(at(after))
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for schedule in [
+            "",
+            "* * * *",
+            "* * * * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * * 13 *",
+            "* * * * 8",
+            "*/0 * * * *",
+            "10-5 * * * *",
+            "a * * * *",
+        ] {
+            assert!(
+                schedule.parse::<Schedule>().is_err(),
+                "`{}` should not parse",
+                schedule
+            );
+        }
+    }
+
+    #[test]
+    fn next_after() {
+        assert_eq!(
+            next("* * * * *", "2022-07-01T10:00:30Z"),
+            Some(at("2022-07-01T10:01:00Z"))
+        );
+        assert_eq!(
+            next("0 * * * *", "2022-07-01T10:00:00Z"),
+            Some(at("2022-07-01T11:00:00Z"))
+        );
+        assert_eq!(
+            next("*/15 9-17 * * *", "2022-07-01T17:50:00Z"),
+            Some(at("2022-07-02T09:00:00Z"))
+        );
+        assert_eq!(
+            next("30 4 1,15 * *", "2022-07-02T00:00:00Z"),
+            Some(at("2022-07-15T04:30:00Z"))
+        );
+        assert_eq!(
+            next("0 0 * 2 *", "2022-07-01T00:00:00Z"),
+            Some(at("2023-02-01T00:00:00Z"))
+        );
+        assert_eq!(
+            next("0 0 29 2 *", "2022-07-01T00:00:00Z"),
+            Some(at("2024-02-29T00:00:00Z"))
+        );
+        assert_eq!(next("0 0 30 2 *", "2022-07-01T00:00:00Z"), None);
+    }
+
+    #[test]
+    fn next_after_days_of_week() {
+        // 2022-07-01 is a Friday
+        assert_eq!(
+            next("0 12 * * 0", "2022-07-01T00:00:00Z"),
+            Some(at("2022-07-03T12:00:00Z"))
+        );
+        assert_eq!(
+            next("0 12 * * 7", "2022-07-01T00:00:00Z"),
+            Some(at("2022-07-03T12:00:00Z"))
+        );
+        assert_eq!(
+            next("0 12 * * 1-5", "2022-07-01T13:00:00Z"),
+            Some(at("2022-07-04T12:00:00Z"))
+        );
+        // Either the day of the month or the day of the week
+        assert_eq!(
+            next("0 12 10 * 1", "2022-07-01T13:00:00Z"),
+            Some(at("2022-07-04T12:00:00Z"))
+        );
+        // A day with a step on `*` still has to match along with the other
+        assert_eq!(
+            next("0 12 */2 * 1", "2022-07-01T13:00:00Z"),
+            Some(at("2022-07-11T12:00:00Z"))
+        );
+    }
+}
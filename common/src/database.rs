@@ -1,10 +1,35 @@
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Type {
     AwsRds(AwsRdsEngine),
     Shared,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum AwsRdsEngine {
     Postgres,
     MySql,
     MariaDB,
 }
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::AwsRds(engine) => write!(f, "AWS RDS {}", engine),
+            Type::Shared => write!(f, "Shared Postgres"),
+        }
+    }
+}
+
+impl Display for AwsRdsEngine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AwsRdsEngine::Postgres => write!(f, "Postgres"),
+            AwsRdsEngine::MySql => write!(f, "MySQL"),
+            AwsRdsEngine::MariaDB => write!(f, "MariaDB"),
+        }
+    }
+}
@@ -1,13 +1,16 @@
+pub mod cron;
 pub mod database;
 pub mod project;
+pub mod resource;
 
 use std::{
     collections::BTreeMap,
     fmt::{Display, Formatter},
+    str::FromStr,
 };
 
 use chrono::{DateTime, Utc};
-use log::Level;
+use log::{Level, LevelFilter};
 use rocket::Responder;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -16,6 +19,10 @@ use crate::project::ProjectName;
 
 pub const SHUTTLE_PROJECT_HEADER: &str = "Shuttle-Project";
 
+/// Target of the logs a service sends about the runs of its cron jobs, see
+/// [`CronJobMeta::from_fields`]
+pub const CRON_LOG_TARGET: &str = "shuttle_service::cron";
+
 #[cfg(debug_assertions)]
 pub const API_URL_DEFAULT: &str = "http://localhost:8001";
 
@@ -37,9 +44,39 @@ pub struct DeploymentMeta {
     pub project: ProjectName,
     pub state: DeploymentStateMeta,
     pub host: String,
+    /// Not sent along with the metadata, see [`BuildEvent`] for following
+    /// the build instead
+    #[serde(skip_serializing)]
     pub build_logs: Option<String>,
-    pub runtime_logs: BTreeMap<DateTime<Utc>, LogItem>,
-    pub database_deployment: Option<DatabaseReadyInfo>,
+    /// The most verbose level of runtime logs the service keeps
+    pub log_level: LevelFilter,
+    pub protocol: Protocol,
+    /// Public port raw TCP connections to the service come in on, only set
+    /// for [`Protocol::Tcp`] services
+    pub tcp_port: Option<Port>,
+    /// Path the service answers with a 2xx status on once it is ready to
+    /// take requests. Without one, the service is ready once its port
+    /// accepts connections
+    pub health_path: Option<String>,
+    /// Number of times the service got restarted after it crashed
+    #[serde(default)]
+    pub restarts: u32,
+    /// Why the service crashed the last time, if it ever did
+    pub last_crash: Option<String>,
+    /// The resources provisioned for the service, at most one of each type
+    /// for each name
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub resources: Vec<resource::Response>,
+    /// The cron jobs of the service, as far as it reported on them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cron_jobs: Vec<CronJobMeta>,
+    /// Most resources the service may use, as set for its project when the
+    /// service got started
+    #[serde(default)]
+    pub limits: ResourceLimits,
+    /// Resources the service was using when it was last checked on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<ResourceUsage>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -60,8 +97,16 @@ impl DeploymentMeta {
             state,
             host,
             build_logs: None,
-            runtime_logs: BTreeMap::new(),
-            database_deployment: None,
+            log_level: LevelFilter::Info,
+            protocol: Protocol::Http,
+            tcp_port: None,
+            health_path: None,
+            restarts: 0,
+            last_crash: None,
+            resources: Vec::new(),
+            cron_jobs: Vec::new(),
+            limits: ResourceLimits::default(),
+            usage: None,
             created_at: Utc::now(),
         }
     }
@@ -73,15 +118,39 @@ impl DeploymentMeta {
 
 impl Display for DeploymentMeta {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let db = {
-            if let Some(info) = &self.database_deployment {
+        let address = match self.tcp_port {
+            Some(port) => format!("tcp://{}:{}", self.host, port),
+            None => format!("https://{}", self.host),
+        };
+        let resources: String = self
+            .resources
+            .iter()
+            .map(|resource| {
                 format!(
-                    "\n        Database URI:       {}",
-                    info.connection_string_public()
+                    "\n        Resource:           {}\n        Resource URI:       {}",
+                    resource.request(),
+                    resource.connection_string_public
                 )
-            } else {
-                "".to_string()
-            }
+            })
+            .collect();
+        let crashes = match &self.last_crash {
+            Some(last_crash) => format!(
+                "\n        Restarts:           {} (last crash: {})",
+                self.restarts, last_crash
+            ),
+            None => "".to_string(),
+        };
+        let cron_jobs: String = self
+            .cron_jobs
+            .iter()
+            .map(|job| format!("\n        Cron Job:           {}", job))
+            .collect();
+        let usage = match &self.usage {
+            Some(usage) => format!(
+                "\n        Resources:          {}",
+                usage.describe(&self.limits)
+            ),
+            None => "".to_string(),
         };
         write!(
             f,
@@ -89,10 +158,223 @@ impl Display for DeploymentMeta {
         Project:            {}
         Deployment Id:      {}
         Deployment Status:  {}
-        Host:               https://{}
-        Created At:         {}{}
+        Host:               {}
+        Created At:         {}{}{}{}{}
         "#,
-            self.project, self.id, self.state, self.host, self.created_at, db
+            self.project,
+            self.id,
+            self.state,
+            address,
+            self.created_at,
+            crashes,
+            usage,
+            resources,
+            cron_jobs
+        )
+    }
+}
+
+/// Most resources the service of a deployment may use. Unlimited when not set.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceLimits {
+    /// Memory the service may allocate, in megabytes
+    #[serde(default)]
+    pub memory_mb: Option<u64>,
+    /// Processor time the service may use, in percent of a single core
+    #[serde(default)]
+    pub cpu_percent: Option<u64>,
+    /// Files and sockets the service may have open at the same time
+    #[serde(default)]
+    pub open_files: Option<u64>,
+}
+
+/// Resources the service of a deployment is using.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceUsage {
+    /// Memory the service has allocated, in megabytes, counted the same way
+    /// as for its limit
+    pub memory_mb: u64,
+    /// Processor time the service took so far, in seconds
+    pub cpu_secs: u64,
+    /// Files and sockets the service has open
+    pub open_files: u64,
+}
+
+impl ResourceUsage {
+    /// Describes the usage next to the limits it is held to, pointing out the
+    /// limits which were reached. The cpu limit caps a share of a core rather
+    /// than the time taken, so it never gets reached.
+    pub fn describe(&self, limits: &ResourceLimits) -> String {
+        let describe = |name: &str, used: u64, limit: Option<u64>, unit: &str| match limit {
+            Some(limit) if used >= limit => {
+                format!("{name} {used}{unit} of {limit}{unit} (limit reached)")
+            }
+            Some(limit) => format!("{name} {used}{unit} of {limit}{unit}"),
+            None => format!("{name} {used}{unit}"),
+        };
+        let cpu = match limits.cpu_percent {
+            Some(cpu_percent) => format!(
+                "cpu {} s at {}% of a core at most",
+                self.cpu_secs, cpu_percent
+            ),
+            None => format!("cpu {} s", self.cpu_secs),
+        };
+
+        [
+            describe("memory", self.memory_mb, limits.memory_mb, " MB"),
+            cpu,
+            describe("open files", self.open_files, limits.open_files, ""),
+        ]
+        .join(", ")
+    }
+}
+
+/// A periodic job declared with `#[shuttle_service::cron]` and how its runs
+/// went so far
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CronJobMeta {
+    pub name: String,
+    pub schedule: String,
+    pub last_run: Option<DateTime<Utc>>,
+    pub next_run: Option<DateTime<Utc>>,
+    /// Why the last run failed, if it did
+    pub last_error: Option<String>,
+}
+
+impl CronJobMeta {
+    /// Describes the job as the fields of a log, which is how the service
+    /// gets it across to the api.
+    pub fn to_fields(&self) -> BTreeMap<String, String> {
+        let mut fields = BTreeMap::new();
+        fields.insert("job".to_string(), self.name.clone());
+        fields.insert("schedule".to_string(), self.schedule.clone());
+
+        if let Some(last_run) = self.last_run {
+            fields.insert("last_run".to_string(), last_run.to_rfc3339());
+        }
+        if let Some(next_run) = self.next_run {
+            fields.insert("next_run".to_string(), next_run.to_rfc3339());
+        }
+        if let Some(last_error) = &self.last_error {
+            fields.insert("error".to_string(), last_error.clone());
+        }
+
+        fields
+    }
+
+    /// The opposite of [`CronJobMeta::to_fields`]. Returns `None` if the
+    /// fields do not describe a job.
+    pub fn from_fields(fields: &BTreeMap<String, String>) -> Option<Self> {
+        let datetime = |key: &str| {
+            fields
+                .get(key)
+                .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+                .map(|datetime| datetime.with_timezone(&Utc))
+        };
+
+        Some(Self {
+            name: fields.get("job")?.clone(),
+            schedule: fields.get("schedule")?.clone(),
+            last_run: datetime("last_run"),
+            next_run: datetime("next_run"),
+            last_error: fields.get("error").cloned(),
+        })
+    }
+}
+
+impl Display for CronJobMeta {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let format = |datetime: Option<DateTime<Utc>>| {
+            datetime.map_or_else(
+                || "-".to_string(),
+                |datetime| datetime.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            )
+        };
+
+        write!(
+            f,
+            "{} ({})  last run: {}  next run: {}",
+            self.name,
+            self.schedule,
+            format(self.last_run),
+            format(self.next_run)
+        )?;
+
+        if let Some(last_error) = &self.last_error {
+            write!(f, "  failed: {}", last_error)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// How clients reach a deployed service
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    /// Requests are proxied to the service based on their `Host` header
+    Http,
+    /// Raw TCP connections to a public port of the project are passed
+    /// through to the service as they are
+    Tcp,
+}
+
+impl Default for Protocol {
+    fn default() -> Self {
+        Protocol::Http
+    }
+}
+
+impl Display for Protocol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Protocol::Http => write!(f, "http"),
+            Protocol::Tcp => write!(f, "tcp"),
+        }
+    }
+}
+
+impl FromStr for Protocol {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "http" => Ok(Protocol::Http),
+            "tcp" => Ok(Protocol::Tcp),
+            other => Err(anyhow::anyhow!("unknown protocol: {}", other)),
+        }
+    }
+}
+
+/// A short overview of a deployment, as returned when listing the
+/// deployment history of a project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeploymentSummary {
+    pub id: DeploymentId,
+    pub state: DeploymentStateMeta,
+    pub host: String,
+    #[serde(default)]
+    pub protocol: Protocol,
+    /// Public port of the service, only set for [`Protocol::Tcp`] services
+    #[serde(default)]
+    pub tcp_port: Option<Port>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Display for DeploymentSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let address = match (self.protocol, self.tcp_port) {
+            (Protocol::Tcp, Some(port)) => format!("tcp://{}:{}", self.host, port),
+            _ => format!("https://{}", self.host),
+        };
+
+        write!(
+            f,
+            "{}  {}  {:<10}  {}",
+            self.created_at.format("%Y-%m-%dT%H:%M:%SZ"),
+            self.id,
+            self.state,
+            address
         )
     }
 }
@@ -153,12 +435,14 @@ impl DatabaseReadyInfo {
 }
 
 /// A label used to represent the deployment state in `DeploymentMeta`
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum DeploymentStateMeta {
     Queued,
     Built,
     Loaded,
     Deployed,
+    /// The service stopped running after it was deployed, and gets restarted
+    Crashed(String),
     Error(String),
     Deleted,
 }
@@ -170,6 +454,7 @@ impl Display for DeploymentStateMeta {
             DeploymentStateMeta::Built => "BUILT".to_string(),
             DeploymentStateMeta::Loaded => "LOADED".to_string(),
             DeploymentStateMeta::Deployed => "DEPLOYED".to_string(),
+            DeploymentStateMeta::Crashed(msg) => format!("CRASHED: {}", &msg),
             DeploymentStateMeta::Error(msg) => format!("ERROR: {}", &msg),
             DeploymentStateMeta::Deleted => "DELETED".to_string(),
         };
@@ -212,4 +497,160 @@ pub struct LogItem {
     pub body: String,
     pub level: Level,
     pub target: String,
+    /// Structured key/value fields recorded with the log
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, String>,
+    /// The spans the log was emitted in, outermost first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spans: Vec<LogSpan>,
+}
+
+/// A `tracing` span a log was emitted in
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LogSpan {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, String>,
+}
+
+/// Criteria for picking out runtime logs. Every criterion that is set has to
+/// match for a log to be picked.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct LogFilter {
+    /// The most verbose level to pick
+    pub level: Option<Level>,
+    /// Prefix of the targets to pick, usually a module path
+    pub target: Option<String>,
+    /// Only pick logs from this moment on
+    pub since: Option<DateTime<Utc>>,
+    /// Only pick logs up to and including this moment
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, datetime: &DateTime<Utc>, item: &LogItem) -> bool {
+        self.level.map_or(true, |level| item.level <= level)
+            && self
+                .target
+                .as_ref()
+                .map_or(true, |target| item.target.starts_with(target.as_str()))
+            && self.since.map_or(true, |since| *datetime >= since)
+            && self.until.map_or(true, |until| *datetime <= until)
+    }
+}
+
+/// A runtime log of a deployment as the api stores it
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RuntimeLog {
+    /// Unique among all logs and increasing in the order they came in, which
+    /// makes it usable for paging through them
+    pub id: i64,
+    pub datetime: DateTime<Utc>,
+    pub item: LogItem,
+}
+
+/// An event on the build stream of a deployment, as it is pushed to clients
+/// following the build
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum BuildEvent {
+    /// A line of build output, without its line ending
+    Line(String),
+    /// The deployment moved on to a new stage
+    State(DeploymentStateMeta),
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use log::Level;
+
+    use super::{
+        CronJobMeta, DeploymentStateMeta, DeploymentSummary, LogFilter, LogItem, Protocol,
+        ResourceLimits, ResourceUsage,
+    };
+
+    fn item(level: Level, target: &str) -> LogItem {
+        LogItem {
+            body: "hello".to_string(),
+            level,
+            target: target.to_string(),
+            fields: Default::default(),
+            spans: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn log_filter_matches() {
+        let now = Utc::now();
+        let filter = LogFilter {
+            level: Some(Level::Debug),
+            target: Some("app::db".to_string()),
+            since: Some(now - Duration::minutes(1)),
+            until: Some(now),
+        };
+
+        assert!(filter.matches(&now, &item(Level::Warn, "app::db::pool")));
+        assert!(filter.matches(&now, &item(Level::Debug, "app::db")));
+        assert!(!filter.matches(&now, &item(Level::Trace, "app::db")));
+        assert!(!filter.matches(&now, &item(Level::Info, "app::http")));
+        assert!(!filter.matches(&(now - Duration::minutes(2)), &item(Level::Info, "app::db")));
+        assert!(!filter.matches(&(now + Duration::seconds(1)), &item(Level::Info, "app::db")));
+        assert!(LogFilter::default().matches(&now, &item(Level::Trace, "other")));
+    }
+
+    #[test]
+    fn deployment_summary_display() {
+        let mut summary = DeploymentSummary {
+            id: "6f0d6c8e-1b4e-4f0a-9d3c-4bb5b0b8a8a1".parse().unwrap(),
+            state: DeploymentStateMeta::Deployed,
+            host: "game.shuttleapp.rs".to_string(),
+            protocol: Protocol::Http,
+            tcp_port: None,
+            created_at: "2022-06-01T00:00:00Z".parse().unwrap(),
+        };
+
+        assert!(summary
+            .to_string()
+            .ends_with("  https://game.shuttleapp.rs"));
+
+        summary.protocol = Protocol::Tcp;
+        summary.tcp_port = Some(9000);
+
+        assert!(summary
+            .to_string()
+            .ends_with("  tcp://game.shuttleapp.rs:9000"));
+    }
+
+    #[test]
+    fn resource_usage_describe() {
+        let usage = ResourceUsage {
+            memory_mb: 12,
+            cpu_secs: 60,
+            open_files: 10,
+        };
+        let limits = ResourceLimits {
+            memory_mb: Some(12),
+            cpu_percent: Some(50),
+            open_files: Some(1024),
+        };
+
+        assert_eq!(
+            usage.describe(&limits),
+            "memory 12 MB of 12 MB (limit reached), cpu 60 s at 50% of a core at most, open files 10 of 1024"
+        );
+    }
+
+    #[test]
+    fn cron_job_fields_round_trip() {
+        let job = CronJobMeta {
+            name: "cleanup".to_string(),
+            schedule: "0 * * * *".to_string(),
+            last_run: Some("2022-07-01T10:00:00Z".parse().unwrap()),
+            next_run: Some("2022-07-01T11:00:00Z".parse().unwrap()),
+            last_error: Some("database is gone".to_string()),
+        };
+
+        assert_eq!(CronJobMeta::from_fields(&job.to_fields()), Some(job));
+        assert_eq!(CronJobMeta::from_fields(&Default::default()), None);
+    }
 }
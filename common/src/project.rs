@@ -12,7 +12,7 @@ use serde::{Deserialize, Deserializer, Serialize};
 /// - It does not start or end with `-`.
 /// - It does not contain any characters outside of the alphanumeric range, except for `-`.
 /// - It is not empty.
-#[derive(Clone, Serialize, Debug, Eq, PartialEq)]
+#[derive(Clone, Serialize, Debug, Eq, PartialEq, Hash)]
 pub struct ProjectName(String);
 
 impl<'de> Deserialize<'de> for ProjectName {
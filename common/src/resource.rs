@@ -0,0 +1,134 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::database;
+
+/// A kind of resource a service can ask to have provisioned for it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Type {
+    Database(database::Type),
+    Redis,
+    MongoDb,
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Database(db_type) => write!(f, "{}", db_type),
+            Type::Redis => write!(f, "Redis"),
+            Type::MongoDb => write!(f, "MongoDB"),
+        }
+    }
+}
+
+/// A resource a service asks to have provisioned for it. A project holds at
+/// most one resource of each type for each name.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Request {
+    pub r#type: Type,
+    /// Tells apart resources of the same type, for services that need more
+    /// than one of them. See [`is_valid_name`] for what names can be.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl Request {
+    pub fn new(r#type: Type, name: Option<String>) -> Self {
+        Self { r#type, name }
+    }
+}
+
+impl Display for Request {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{} ({})", self.r#type, name),
+            None => write!(f, "{}", self.r#type),
+        }
+    }
+}
+
+/// Names of resources end up in the names of databases, users and the like,
+/// so they are kept to lowercase letters and digits, starting with a letter.
+pub fn is_valid_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+}
+
+/// How to reach a resource that got provisioned for a service
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Response {
+    pub r#type: Type,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// For the service to connect with from the network it runs on
+    pub connection_string_private: String,
+    /// For connecting from anywhere else
+    pub connection_string_public: String,
+    /// Whatever else is particular to the kind of resource, like the prefix
+    /// the keys of a Redis user have to start with
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub details: BTreeMap<String, String>,
+}
+
+impl Response {
+    /// The request this is the response to
+    pub fn request(&self) -> Request {
+        Request::new(self.r#type, self.name.clone())
+    }
+
+    /// Whether this is the resource the request is for
+    pub fn is_for(&self, request: &Request) -> bool {
+        self.r#type == request.r#type && self.name == request.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::{self, AwsRdsEngine};
+
+    use super::{is_valid_name, Request, Type};
+
+    #[test]
+    fn names() {
+        assert!(is_valid_name("analytics"));
+        assert!(is_valid_name("app2"));
+
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name("2app"));
+        assert!(!is_valid_name("Analytics"));
+        assert!(!is_valid_name("app-db"));
+        assert!(!is_valid_name("app_db"));
+        assert!(!is_valid_name("db\"; DROP TABLE users"));
+    }
+
+    #[test]
+    fn request_display() {
+        let request = Request::new(
+            Type::Database(database::Type::Shared),
+            Some("analytics".to_string()),
+        );
+        assert_eq!(request.to_string(), "Shared Postgres (analytics)");
+
+        let request = Request::new(Type::Redis, None);
+        assert_eq!(request.to_string(), "Redis");
+    }
+
+    #[test]
+    fn type_display() {
+        assert_eq!(
+            Type::Database(database::Type::Shared).to_string(),
+            "Shared Postgres"
+        );
+        assert_eq!(
+            Type::Database(database::Type::AwsRds(AwsRdsEngine::MariaDB)).to_string(),
+            "AWS RDS MariaDB"
+        );
+        assert_eq!(Type::MongoDb.to_string(), "MongoDB");
+    }
+}
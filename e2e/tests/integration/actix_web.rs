@@ -0,0 +1,19 @@
+use colored::Color;
+
+use crate::helpers;
+
+#[test]
+fn hello_world_actix_web() {
+    let client = helpers::Services::new_docker("hello-world (actix-web)", Color::Cyan);
+    client.deploy("actix-web/hello-world");
+
+    let request_text = client
+        .get("hello")
+        .header("Host", "hello-world-actix-web-app.localhost.local")
+        .send()
+        .unwrap()
+        .text()
+        .unwrap();
+
+    assert_eq!(request_text, "Hello, world!");
+}
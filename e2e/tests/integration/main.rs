@@ -1,7 +1,10 @@
 pub mod helpers;
 
+pub mod actix_web;
 pub mod axum;
 pub mod poem;
 pub mod rocket;
+pub mod salvo;
 pub mod tide;
 pub mod tower;
+pub mod warp;
@@ -0,0 +1,19 @@
+use colored::Color;
+
+use crate::helpers;
+
+#[test]
+fn hello_world_salvo() {
+    let client = helpers::Services::new_docker("hello-world (salvo)", Color::Cyan);
+    client.deploy("salvo/hello-world");
+
+    let request_text = client
+        .get("hello")
+        .header("Host", "hello-world-salvo-app.localhost.local")
+        .send()
+        .unwrap()
+        .text()
+        .unwrap();
+
+    assert_eq!(request_text, "Hello, world!");
+}
@@ -0,0 +1,19 @@
+use colored::Color;
+
+use crate::helpers;
+
+#[test]
+fn hello_world_warp() {
+    let client = helpers::Services::new_docker("hello-world (warp)", Color::Cyan);
+    client.deploy("warp/hello-world");
+
+    let request_text = client
+        .get("hello")
+        .header("Host", "hello-world-warp-app.localhost.local")
+        .send()
+        .unwrap()
+        .text()
+        .unwrap();
+
+    assert_eq!(request_text, "Hello, world!");
+}
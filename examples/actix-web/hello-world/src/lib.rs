@@ -0,0 +1,17 @@
+use actix_web::{get, web::ServiceConfig};
+
+#[get("/hello")]
+async fn hello_world() -> &'static str {
+    "Hello, world!"
+}
+
+#[shuttle_service::main]
+async fn actix_web(
+) -> shuttle_service::ShuttleActixWeb<impl FnOnce(&mut ServiceConfig) + Sync + Send + Clone + 'static>
+{
+    let config = move |cfg: &mut ServiceConfig| {
+        cfg.service(hello_world);
+    };
+
+    Ok(config.into())
+}
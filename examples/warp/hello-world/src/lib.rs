@@ -0,0 +1,8 @@
+use warp::{Filter, Reply};
+
+#[shuttle_service::main]
+async fn warp() -> shuttle_service::ShuttleWarp<(impl Reply,)> {
+    let route = warp::path("hello").map(|| "Hello, world!");
+
+    Ok(route.boxed())
+}
@@ -1,5 +1,6 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     tonic_build::compile_protos("../proto/provisioner.proto")?;
+    tonic_build::compile_protos("../proto/runtime.proto")?;
 
     Ok(())
 }
@@ -3,29 +3,95 @@ pub mod provisioner {
 
     use shuttle_common::{
         database::{self, AwsRdsEngine},
-        DatabaseReadyInfo,
+        resource, DatabaseReadyInfo,
     };
+    use tonic::Status;
 
     tonic::include_proto!("provisioner");
 
-    impl From<DatabaseResponse> for DatabaseReadyInfo {
-        fn from(response: DatabaseResponse) -> Self {
-            DatabaseReadyInfo::new(
-                response.engine,
-                response.username,
-                response.password,
-                response.database_name,
-                response.port,
-                response.address_private,
-                response.address_public,
-            )
+    impl From<DatabaseReadyInfo> for ResourceResponse {
+        fn from(info: DatabaseReadyInfo) -> Self {
+            Self {
+                connection_string_private: info.connection_string_private(),
+                connection_string_public: info.connection_string_public(),
+                details: Default::default(),
+            }
+        }
+    }
+
+    impl ResourceResponse {
+        /// Gets how to reach the resource requested, given this is the response to it
+        pub fn into_resource(self, request: resource::Request) -> resource::Response {
+            resource::Response {
+                r#type: request.r#type,
+                name: request.name,
+                connection_string_private: self.connection_string_private,
+                connection_string_public: self.connection_string_public,
+                details: self.details.into_iter().collect(),
+            }
+        }
+    }
+
+    impl ResourceRequest {
+        /// The name of the resource requested, if it has one
+        pub fn name(&self) -> Option<&str> {
+            if self.name.is_empty() {
+                None
+            } else {
+                Some(&self.name)
+            }
+        }
+    }
+
+    impl From<resource::Type> for resource_request::ResourceType {
+        fn from(r#type: resource::Type) -> Self {
+            match r#type {
+                resource::Type::Database(db_type) => {
+                    resource_request::ResourceType::Database(DatabaseType {
+                        db_type: Some(db_type.into()),
+                    })
+                }
+                resource::Type::Redis => resource_request::ResourceType::Redis(RedisConfig {}),
+                resource::Type::MongoDb => {
+                    resource_request::ResourceType::Mongodb(MongoDbConfig {})
+                }
+            }
+        }
+    }
+
+    impl TryFrom<resource_request::ResourceType> for resource::Type {
+        type Error = Status;
+
+        fn try_from(r#type: resource_request::ResourceType) -> Result<Self, Self::Error> {
+            let missing = || Status::invalid_argument("missing the type of resource to provision");
+
+            let r#type = match r#type {
+                resource_request::ResourceType::Database(DatabaseType { db_type }) => {
+                    let db_type = match db_type.ok_or_else(missing)? {
+                        database_type::DbType::Shared(_) => database::Type::Shared,
+                        database_type::DbType::AwsRds(AwsRds { engine }) => {
+                            let engine = match engine.ok_or_else(missing)? {
+                                aws_rds::Engine::Postgres(_) => AwsRdsEngine::Postgres,
+                                aws_rds::Engine::Mysql(_) => AwsRdsEngine::MySql,
+                                aws_rds::Engine::Mariadb(_) => AwsRdsEngine::MariaDB,
+                            };
+                            database::Type::AwsRds(engine)
+                        }
+                    };
+                    resource::Type::Database(db_type)
+                }
+                resource_request::ResourceType::Redis(_) => resource::Type::Redis,
+                resource_request::ResourceType::Mongodb(_) => resource::Type::MongoDb,
+            };
+
+            Ok(r#type)
         }
     }
 
-    impl From<database::Type> for database_request::DbType {
+    impl From<database::Type> for database_type::DbType {
         fn from(db_type: database::Type) -> Self {
             match db_type {
-                database::Type::Shared => database_request::DbType::Shared(String::new()),
+                database::Type::Shared => database_type::DbType::Shared(String::new()),
                 database::Type::AwsRds(engine) => {
                     let config = RdsConfig {};
                     let engine = match engine {
@@ -33,7 +99,7 @@ pub mod provisioner {
                         AwsRdsEngine::MariaDB => aws_rds::Engine::Mariadb(config),
                         AwsRdsEngine::MySql => aws_rds::Engine::Mysql(config),
                     };
-                    database_request::DbType::AwsRds(AwsRds {
+                    database_type::DbType::AwsRds(AwsRds {
                         engine: Some(engine),
                     })
                 }
@@ -51,3 +117,15 @@ pub mod provisioner {
         }
     }
 }
+
+pub mod runtime {
+    tonic::include_proto!("runtime");
+
+    /// Environment variable a runner gets the secret it authenticates to the
+    /// supervisor with from
+    pub const SECRET_ENV: &str = "SHUTTLE_RUNNER_SECRET";
+
+    /// Metadata key runners send their secret to the supervisor under with
+    /// every request
+    pub const SECRET_METADATA_KEY: &str = "x-runner-secret";
+}
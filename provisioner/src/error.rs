@@ -17,9 +17,21 @@ pub enum Error {
     #[error("failed to create DB")]
     CreateDB(String),
 
+    #[error("failed to create Redis user")]
+    CreateRedisUser(String),
+
+    #[error("failed to create MongoDB user")]
+    CreateMongoDbUser(String),
+
     #[error("unexpected error")]
     Unexpected(#[from] sqlx::Error),
 
+    #[error("unexpected Redis error")]
+    UnexpectedRedis(#[from] redis::RedisError),
+
+    #[error("unexpected MongoDB error")]
+    UnexpectedMongoDb(#[from] mongodb::error::Error),
+
     #[error("failed to create RDS instance")]
     CreateRDSInstance(#[from] SdkError<CreateDBInstanceError>),
 
@@ -35,6 +47,6 @@ unsafe impl Send for Error {}
 impl From<Error> for Status {
     fn from(err: Error) -> Self {
         error!(error = &err as &dyn std::error::Error, "provision failed");
-        Status::internal("failed to provision a database")
+        Status::internal("failed to provision a resource")
     }
 }
@@ -5,11 +5,17 @@ use aws_config::timeout;
 use aws_sdk_rds::{error::ModifyDBInstanceErrorKind, model::DbInstance, types::SdkError, Client};
 use aws_smithy_types::tristate::TriState;
 pub use error::Error;
+use mongodb::{
+    bson::doc,
+    options::{ClientOptions, ServerAddress},
+};
 use rand::Rng;
+use shuttle_common::{resource, DatabaseReadyInfo};
 use shuttle_proto::provisioner::provisioner_server::Provisioner;
 pub use shuttle_proto::provisioner::provisioner_server::ProvisionerServer;
 use shuttle_proto::provisioner::{
-    aws_rds, database_request::DbType, AwsRds, DatabaseRequest, DatabaseResponse,
+    aws_rds, database_type::DbType, resource_request::ResourceType, AwsRds, DatabaseType,
+    ResourceRequest, ResourceResponse,
 };
 use sqlx::{postgres::PgPoolOptions, PgPool};
 use tokio::time::sleep;
@@ -25,19 +31,45 @@ const RDS_SUBNET_GROUP: &str = "shuttle_rds";
 
 pub struct MyProvisioner {
     pool: PgPool,
+    redis_client: redis::Client,
+    mongodb_client: mongodb::Client,
+    mongodb_address: (String, String),
     rds_client: aws_sdk_rds::Client,
     fqdn: String,
     internal_address: String,
 }
 
 impl MyProvisioner {
-    pub async fn new(db_uri: &str, fqdn: String, internal_address: String) -> sqlx::Result<Self> {
+    pub async fn new(
+        db_uri: &str,
+        redis_uri: &str,
+        mongodb_uri: &str,
+        fqdn: String,
+        internal_address: String,
+    ) -> Result<Self, Error> {
         let pool = PgPoolOptions::new()
             .min_connections(4)
             .max_connections(12)
             .connect_timeout(Duration::from_secs(60))
             .connect_lazy(db_uri)?;
 
+        // Only connects once a resource is requested
+        let redis_client = redis::Client::open(redis_uri)?;
+        let mongodb_options = ClientOptions::parse(mongodb_uri).await?;
+
+        // Services reach the instance the same way this provisioner does
+        let mongodb_address = match mongodb_options.hosts.first() {
+            Some(ServerAddress::Tcp { host, port }) => {
+                (host.clone(), port.unwrap_or(27017).to_string())
+            }
+            host => {
+                return Err(Error::Plain(format!(
+                    "cannot hand out mongodb instance at {host:?}"
+                )))
+            }
+        };
+        let mongodb_client = mongodb::Client::with_options(mongodb_options)?;
+
         // Default timeout is too long so lowering it
         let api_timeout_config = timeout::Api::new()
             .with_call_timeout(TriState::Set(Duration::from_secs(120)))
@@ -53,29 +85,37 @@ impl MyProvisioner {
 
         Ok(Self {
             pool,
+            redis_client,
+            mongodb_client,
+            mongodb_address,
             rds_client,
             fqdn,
             internal_address,
         })
     }
 
-    pub async fn request_shared_db(&self, project_name: &str) -> Result<DatabaseResponse, Error> {
-        let (username, password) = self.shared_role(project_name).await?;
-        let database_name = self.shared_db(project_name, &username).await?;
-
-        Ok(DatabaseResponse {
-            engine: "postgres".to_string(),
+    pub async fn request_shared_db(
+        &self,
+        project_name: &str,
+        name: Option<&str>,
+    ) -> Result<DatabaseReadyInfo, Error> {
+        let resource_id = resource_id(project_name, name);
+        let (username, password) = self.shared_role(&resource_id).await?;
+        let database_name = self.shared_db(&resource_id, &username).await?;
+
+        Ok(DatabaseReadyInfo::new(
+            "postgres".to_string(),
             username,
             password,
             database_name,
-            address_private: self.internal_address.clone(),
-            address_public: self.fqdn.clone(),
-            port: "5432".to_string(),
-        })
+            "5432".to_string(),
+            self.internal_address.clone(),
+            self.fqdn.clone(),
+        ))
     }
 
-    async fn shared_role(&self, project_name: &str) -> Result<(String, String), Error> {
-        let username = format!("user-{project_name}");
+    async fn shared_role(&self, resource_id: &str) -> Result<(String, String), Error> {
+        let username = format!("user-{resource_id}");
         let password = generate_password();
 
         let matching_user = sqlx::query("SELECT rolname FROM pg_roles WHERE rolname = $1")
@@ -110,8 +150,8 @@ impl MyProvisioner {
         Ok((username, password))
     }
 
-    async fn shared_db(&self, project_name: &str, username: &str) -> Result<String, Error> {
-        let database_name = format!("db-{project_name}");
+    async fn shared_db(&self, resource_id: &str, username: &str) -> Result<String, Error> {
+        let database_name = format!("db-{resource_id}");
 
         let matching_db = sqlx::query("SELECT datname FROM pg_database WHERE datname = $1")
             .bind(&database_name)
@@ -133,11 +173,115 @@ impl MyProvisioner {
         Ok(database_name)
     }
 
+    pub async fn request_shared_redis(
+        &self,
+        project_name: &str,
+        name: Option<&str>,
+    ) -> Result<ResourceResponse, Error> {
+        let resource_id = resource_id(project_name, name);
+        let username = format!("user-{resource_id}");
+        let password = generate_password();
+        let key_prefix = format!("{resource_id}:");
+
+        let mut connection = self.redis_client.get_async_connection().await?;
+
+        // Resetting the user first also cycles its password when it exists already. The user can
+        // only touch the keys and channels under its prefix and cannot administer the instance.
+        info!("creating or updating redis user");
+        redis::cmd("ACL")
+            .arg("SETUSER")
+            .arg(&username)
+            .arg("reset")
+            .arg("on")
+            .arg(format!(">{password}"))
+            .arg(format!("~{key_prefix}*"))
+            .arg(format!("&{key_prefix}*"))
+            .arg("+@all")
+            .arg("-@admin")
+            .arg("-@dangerous")
+            .query_async::<_, ()>(&mut connection)
+            .await
+            .map_err(|e| Error::CreateRedisUser(e.to_string()))?;
+
+        // Services reach the instance the same way this provisioner does
+        let (address, port) = match &self.redis_client.get_connection_info().addr {
+            redis::ConnectionAddr::Tcp(host, port) => (host.clone(), port.to_string()),
+            addr => {
+                return Err(Error::Plain(format!(
+                    "cannot hand out redis instance at {addr:?}"
+                )))
+            }
+        };
+
+        Ok(ResourceResponse {
+            connection_string_private: format!("redis://{username}:{password}@{address}:{port}"),
+            connection_string_public: format!("redis://{username}:{password}@{}:{port}", self.fqdn),
+            details: [("key_prefix".to_string(), key_prefix)].into(),
+        })
+    }
+
+    pub async fn request_shared_mongodb(
+        &self,
+        project_name: &str,
+        name: Option<&str>,
+    ) -> Result<DatabaseReadyInfo, Error> {
+        let resource_id = resource_id(project_name, name);
+        let username = format!("user-{resource_id}");
+        let password = generate_password();
+        let database_name = format!("mongodb-{resource_id}");
+
+        // The user lives in the database of the project, so that the database in the connection
+        // string is also the one to authenticate against
+        let database = self.mongodb_client.database(&database_name);
+
+        let matching_user = database
+            .run_command(doc! { "usersInfo": &username }, None)
+            .await?
+            .get_array("users")
+            .map(|users| !users.is_empty())
+            .unwrap_or_default();
+
+        if !matching_user {
+            info!("creating new mongodb user");
+
+            database
+                .run_command(
+                    doc! {
+                        "createUser": &username,
+                        "pwd": &password,
+                        "roles": [{ "role": "readWrite", "db": &database_name }],
+                    },
+                    None,
+                )
+                .await
+                .map_err(|e| Error::CreateMongoDbUser(e.to_string()))?;
+        } else {
+            info!("cycling password of mongodb user");
+
+            database
+                .run_command(doc! { "updateUser": &username, "pwd": &password }, None)
+                .await
+                .map_err(|e| Error::CreateMongoDbUser(e.to_string()))?;
+        }
+
+        let (address, port) = self.mongodb_address.clone();
+
+        Ok(DatabaseReadyInfo::new(
+            "mongodb".to_string(),
+            username,
+            password,
+            database_name,
+            port,
+            address,
+            self.fqdn.clone(),
+        ))
+    }
+
     async fn request_aws_rds(
         &self,
         project_name: &str,
         engine: aws_rds::Engine,
-    ) -> Result<DatabaseResponse, Error> {
+    ) -> Result<DatabaseReadyInfo, Error> {
         let client = &self.rds_client;
 
         let password = generate_password();
@@ -202,44 +346,94 @@ impl MyProvisioner {
             .address
             .expect("endpoint to have an address");
 
-        Ok(DatabaseResponse {
-            engine: engine.to_string(),
-            username: instance
+        Ok(DatabaseReadyInfo::new(
+            engine.to_string(),
+            instance
                 .master_username
                 .expect("instance to have a username"),
             password,
-            database_name: instance
+            instance
                 .db_name
                 .expect("instance to have a default database"),
-            address_private: address.clone(),
-            address_public: address,
-            port: engine_to_port(engine),
-        })
+            engine_to_port(engine),
+            address.clone(),
+            address,
+        ))
     }
 }
 
 #[tonic::async_trait]
 impl Provisioner for MyProvisioner {
     #[tracing::instrument(skip(self))]
-    async fn provision_database(
+    async fn provision(
         &self,
-        request: Request<DatabaseRequest>,
-    ) -> Result<Response<DatabaseResponse>, Status> {
-        let request = request.into_inner();
-        let db_type = request.db_type.unwrap();
-
-        let reply = match db_type {
-            DbType::Shared(_) => self.request_shared_db(&request.project_name).await?,
-            DbType::AwsRds(AwsRds { engine }) => {
-                self.request_aws_rds(&request.project_name, engine.expect("oneof to be set"))
+        request: Request<ResourceRequest>,
+    ) -> Result<Response<ResourceResponse>, Status> {
+        let mut request = request.into_inner();
+        let resource_type = request
+            .resource_type
+            .take()
+            .ok_or_else(|| Status::invalid_argument("missing the type of resource to provision"))?;
+
+        let name = request.name();
+        if let Some(name) = name {
+            if !resource::is_valid_name(name) {
+                return Err(Status::invalid_argument(format!(
+                    "invalid resource name '{name}': names are lowercase letters and digits, starting with a letter"
+                )));
+            }
+        }
+
+        let reply = match resource_type {
+            ResourceType::Database(DatabaseType { db_type }) => {
+                let db_type = db_type
+                    .ok_or_else(|| Status::invalid_argument("missing the type of database"))?;
+
+                match db_type {
+                    DbType::Shared(_) => {
+                        self.request_shared_db(&request.project_name, name).await?
+                    }
+                    DbType::AwsRds(AwsRds { engine }) => {
+                        let engine = engine.ok_or_else(|| {
+                            Status::invalid_argument("missing the engine of the database")
+                        })?;
+
+                        // Instance identifiers have no room to tell the name apart from the project
+                        if name.is_some() {
+                            return Err(Status::invalid_argument(
+                                "AWS RDS databases cannot be named",
+                            ));
+                        }
+
+                        self.request_aws_rds(&request.project_name, engine).await?
+                    }
+                }
+                .into()
+            }
+            ResourceType::Redis(_) => {
+                self.request_shared_redis(&request.project_name, name)
                     .await?
             }
+            ResourceType::Mongodb(_) => self
+                .request_shared_mongodb(&request.project_name, name)
+                .await?
+                .into(),
         };
 
         Ok(Response::new(reply))
     }
 }
 
+/// Identifies a resource of a project in the names of the users and databases made for it. Project
+/// names cannot contain an underscore, so the resources of one project never get the names of the
+/// resources of another.
+fn resource_id(project_name: &str, name: Option<&str>) -> String {
+    match name {
+        Some(name) => format!("{project_name}_{name}"),
+        None => project_name.to_string(),
+    }
+}
+
 fn generate_password() -> String {
     rand::thread_rng()
         .sample_iter(&rand::distributions::Alphanumeric)
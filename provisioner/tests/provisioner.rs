@@ -110,16 +110,25 @@ fn exec(query: &str) -> String {
 
 #[tokio::test]
 async fn shared_db_role_does_not_exist() {
-    let provisioner = MyProvisioner::new(&PG.uri, "fqdn".to_string(), "internal".to_string())
-        .await
-        .unwrap();
+    let provisioner = MyProvisioner::new(
+        &PG.uri,
+        "redis://localhost",
+        "mongodb://localhost",
+        "fqdn".to_string(),
+        "internal".to_string(),
+    )
+    .await
+    .unwrap();
 
     assert_eq!(
         exec("SELECT rolname FROM pg_roles WHERE rolname = 'user-not_exist'"),
         ""
     );
 
-    provisioner.request_shared_db("not_exist").await.unwrap();
+    provisioner
+        .request_shared_db("not_exist", None)
+        .await
+        .unwrap();
 
     assert_eq!(
         exec("SELECT rolname FROM pg_roles WHERE rolname = 'user-not_exist'"),
@@ -129,9 +138,15 @@ async fn shared_db_role_does_not_exist() {
 
 #[tokio::test]
 async fn shared_db_role_does_exist() {
-    let provisioner = MyProvisioner::new(&PG.uri, "fqdn".to_string(), "internal".to_string())
-        .await
-        .unwrap();
+    let provisioner = MyProvisioner::new(
+        &PG.uri,
+        "redis://localhost",
+        "mongodb://localhost",
+        "fqdn".to_string(),
+        "internal".to_string(),
+    )
+    .await
+    .unwrap();
 
     exec("CREATE ROLE \"user-exist\" WITH LOGIN PASSWORD 'temp'");
     assert_eq!(
@@ -139,7 +154,7 @@ async fn shared_db_role_does_exist() {
         "md5d44ae85dd21bda2a4f9946217adea2cc"
     );
 
-    provisioner.request_shared_db("exist").await.unwrap();
+    provisioner.request_shared_db("exist", None).await.unwrap();
 
     // Make sure password got cycled
     assert_ne!(
@@ -153,28 +168,43 @@ async fn shared_db_role_does_exist() {
     expected = "CreateRole(\"error returned from database: cannot insert multiple commands into a prepared statement\""
 )]
 async fn injection_safe() {
-    let provisioner = MyProvisioner::new(&PG.uri, "fqdn".to_string(), "internal".to_string())
-        .await
-        .unwrap();
+    let provisioner = MyProvisioner::new(
+        &PG.uri,
+        "redis://localhost",
+        "mongodb://localhost",
+        "fqdn".to_string(),
+        "internal".to_string(),
+    )
+    .await
+    .unwrap();
 
     provisioner
-        .request_shared_db("new\"; CREATE ROLE \"injected")
+        .request_shared_db("new\"; CREATE ROLE \"injected", None)
         .await
         .unwrap();
 }
 
 #[tokio::test]
 async fn shared_db_missing() {
-    let provisioner = MyProvisioner::new(&PG.uri, "fqdn".to_string(), "internal".to_string())
-        .await
-        .unwrap();
+    let provisioner = MyProvisioner::new(
+        &PG.uri,
+        "redis://localhost",
+        "mongodb://localhost",
+        "fqdn".to_string(),
+        "internal".to_string(),
+    )
+    .await
+    .unwrap();
 
     assert_eq!(
         exec("SELECT datname FROM pg_database WHERE datname = 'db-missing'"),
         ""
     );
 
-    provisioner.request_shared_db("missing").await.unwrap();
+    provisioner
+        .request_shared_db("missing", None)
+        .await
+        .unwrap();
 
     assert_eq!(
         exec("SELECT datname FROM pg_database WHERE datname = 'db-missing'"),
@@ -184,9 +214,15 @@ async fn shared_db_missing() {
 
 #[tokio::test]
 async fn shared_db_filled() {
-    let provisioner = MyProvisioner::new(&PG.uri, "fqdn".to_string(), "internal".to_string())
-        .await
-        .unwrap();
+    let provisioner = MyProvisioner::new(
+        &PG.uri,
+        "redis://localhost",
+        "mongodb://localhost",
+        "fqdn".to_string(),
+        "internal".to_string(),
+    )
+    .await
+    .unwrap();
 
     exec("CREATE ROLE \"user-filled\" WITH LOGIN PASSWORD 'temp'");
     exec("CREATE DATABASE \"db-filled\" OWNER 'user-filled'");
@@ -195,10 +231,42 @@ async fn shared_db_filled() {
         "db-filled"
     );
 
-    provisioner.request_shared_db("filled").await.unwrap();
+    provisioner.request_shared_db("filled", None).await.unwrap();
 
     assert_eq!(
         exec("SELECT datname FROM pg_database WHERE datname = 'db-filled'"),
         "db-filled"
     );
 }
+
+#[tokio::test]
+async fn shared_db_named() {
+    let provisioner = MyProvisioner::new(
+        &PG.uri,
+        "redis://localhost",
+        "mongodb://localhost",
+        "fqdn".to_string(),
+        "internal".to_string(),
+    )
+    .await
+    .unwrap();
+
+    let app = provisioner.request_shared_db("named", None).await.unwrap();
+    let analytics = provisioner
+        .request_shared_db("named", Some("analytics"))
+        .await
+        .unwrap();
+
+    assert_ne!(
+        app.connection_string_private(),
+        analytics.connection_string_private()
+    );
+    assert_eq!(
+        exec("SELECT datname FROM pg_database WHERE datname LIKE 'db-named%' ORDER BY datname"),
+        "db-named\ndb-named_analytics"
+    );
+    assert_eq!(
+        exec("SELECT rolname FROM pg_roles WHERE rolname = 'user-named_analytics'"),
+        "user-named_analytics"
+    );
+}
@@ -0,0 +1,39 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use log::LevelFilter;
+use shuttle_common::project::ProjectName;
+use shuttle_common::{DeploymentId, Port};
+
+#[derive(Parser)]
+#[clap(name = "shuttle-runner")]
+pub struct Args {
+    /// Shared object the service was built into
+    #[clap(long)]
+    pub(crate) so_path: PathBuf,
+    /// Deployment the service belongs to
+    #[clap(long)]
+    pub(crate) deployment_id: DeploymentId,
+    /// Project the service belongs to
+    #[clap(long)]
+    pub(crate) project: ProjectName,
+    /// Port on localhost the service takes requests on
+    #[clap(long)]
+    pub(crate) port: Port,
+    /// Most verbose level of the service's logs which get kept
+    #[clap(long, default_value = "info")]
+    pub(crate) log_level: LevelFilter,
+    /// Address of the api supervising this runner
+    #[clap(long)]
+    pub(crate) supervisor_uri: String,
+    /// Memory the service may allocate, in megabytes
+    #[clap(long)]
+    pub(crate) max_memory_mb: Option<u64>,
+    /// Files and sockets the service may have open at the same time
+    #[clap(long)]
+    pub(crate) max_open_files: Option<u64>,
+    /// Cgroup holding the service to its cpu limit, which the api moves this
+    /// runner into before the service gets loaded
+    #[clap(long)]
+    pub(crate) cgroup: Option<PathBuf>,
+}
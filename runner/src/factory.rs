@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use shuttle_common::{project::ProjectName, resource, DeploymentId};
+use shuttle_proto::provisioner::ResourceRequest;
+use shuttle_proto::runtime::ProvisionRequest;
+use shuttle_service::Factory;
+use tonic::Request;
+
+use crate::Supervisor;
+
+/// Provisions the resources of a service through the api supervising it.
+pub(crate) struct ShuttleFactory {
+    deployment_id: DeploymentId,
+    project_name: ProjectName,
+    supervisor_client: Supervisor,
+    resources: HashMap<resource::Request, resource::Response>,
+}
+
+impl ShuttleFactory {
+    pub(crate) fn new(
+        supervisor_client: Supervisor,
+        deployment_id: DeploymentId,
+        project_name: ProjectName,
+    ) -> Self {
+        Self {
+            deployment_id,
+            supervisor_client,
+            project_name,
+            resources: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Factory for ShuttleFactory {
+    async fn get_resource(
+        &mut self,
+        request: resource::Request,
+    ) -> Result<resource::Response, shuttle_service::Error> {
+        if let Some(resource) = self.resources.get(&request) {
+            return Ok(resource.clone());
+        }
+
+        let provision_request = Request::new(ProvisionRequest {
+            deployment_id: self.deployment_id.to_string(),
+            resource: Some(ResourceRequest {
+                project_name: self.project_name.to_string(),
+                name: request.name.clone().unwrap_or_default(),
+                resource_type: Some(request.r#type.into()),
+            }),
+        });
+
+        let resource = self
+            .supervisor_client
+            .provision(provision_request)
+            .await
+            .map_err(shuttle_service::error::CustomError::new)?
+            .into_inner()
+            .into_resource(request.clone());
+        self.resources.insert(request.clone(), resource.clone());
+
+        debug!(
+            "giving a {} connection string: {}",
+            request, resource.connection_string_private
+        );
+        Ok(resource)
+    }
+}
@@ -0,0 +1,332 @@
+#[macro_use]
+extern crate log;
+
+mod args;
+mod factory;
+
+use std::fs;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::Path;
+use std::time::Duration;
+
+use clap::Parser;
+use futures::prelude::*;
+use log::LevelFilter;
+use shuttle_proto::runtime::{
+    supervisor_client::SupervisorClient, LogRequest, StartedRequest, SECRET_ENV,
+    SECRET_METADATA_KEY,
+};
+use shuttle_service::loader::Loader;
+use shuttle_service::logger::Log;
+use tokio::io::AsyncReadExt;
+use tokio::sync::mpsc;
+use tonic::metadata::{Ascii, MetadataValue};
+use tonic::service::interceptor::InterceptedService;
+use tonic::service::Interceptor;
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Request, Status};
+
+use crate::args::Args;
+use crate::factory::ShuttleFactory;
+
+/// How often, and how long apart, to look for this runner in its cgroup
+/// before giving up on the api moving it there.
+const CGROUP_WAIT_TRIES: u32 = 100;
+const CGROUP_WAIT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The api supervising this runner, see [`Authenticate`].
+pub(crate) type Supervisor = SupervisorClient<InterceptedService<Channel, Authenticate>>;
+
+/// Sends the secret this runner got from the api along with every request,
+/// which is how the api tells it apart from anything else on the machine.
+#[derive(Clone)]
+pub(crate) struct Authenticate(MetadataValue<Ascii>);
+
+impl Interceptor for Authenticate {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        request
+            .metadata_mut()
+            .insert(SECRET_METADATA_KEY, self.0.clone());
+
+        Ok(request)
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    env_logger::Builder::new()
+        .filter_module("shuttle_runner", LevelFilter::Debug)
+        .filter_module("shuttle_service", LevelFilter::Debug)
+        .init();
+
+    let args: Args = Args::parse();
+    let deployment_id = args.deployment_id;
+
+    let code = match run(args).await {
+        Ok(()) => 0,
+        Err(e) => {
+            error!("service of deployment '{}' failed: {:#}", deployment_id, e);
+            1
+        }
+    };
+
+    // Exit right away since the runtime would wait on the blocking read of stdin
+    std::process::exit(code);
+}
+
+/// Loads the service, reports to the api whether it got started and runs it
+/// until it stops.
+async fn run(args: Args) -> anyhow::Result<()> {
+    let secret = std::env::var(SECRET_ENV)
+        .map_err(|_| anyhow::anyhow!("missing the secret for the api in {}", SECRET_ENV))?;
+    // The service has no business knowing it
+    std::env::remove_var(SECRET_ENV);
+
+    let channel = Endpoint::from_shared(args.supervisor_uri.clone())?
+        .connect()
+        .await?;
+    let mut supervisor = SupervisorClient::with_interceptor(channel, Authenticate(secret.parse()?));
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(forward_logs(supervisor.clone(), rx));
+
+    let loaded = match load(&args) {
+        Ok(loader) => {
+            let mut factory =
+                ShuttleFactory::new(supervisor.clone(), args.deployment_id, args.project);
+            let addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), args.port);
+
+            loader
+                .load(
+                    &mut factory,
+                    addr,
+                    stdin_closed(),
+                    tx,
+                    args.deployment_id,
+                    args.log_level,
+                )
+                .await
+                .map_err(anyhow::Error::from)
+        }
+        Err(e) => Err(e),
+    };
+
+    let report = match &loaded {
+        Ok((_, _, routable)) => StartedRequest {
+            deployment_id: args.deployment_id.to_string(),
+            routable: *routable,
+            error: String::new(),
+        },
+        Err(e) => StartedRequest {
+            deployment_id: args.deployment_id.to_string(),
+            routable: false,
+            error: format!("{:#}", e),
+        },
+    };
+    supervisor.report_started(report).await?;
+
+    // The library has to stay loaded for as long as the service runs
+    let (handle, _so, _) = loaded?;
+    handle.await??;
+
+    Ok(())
+}
+
+/// Loads the service once this process is held to the resource limits of the
+/// deployment, so the service is held to them from the start.
+fn load(args: &Args) -> anyhow::Result<Loader> {
+    limit_resources(args)?;
+
+    Ok(Loader::from_so_file(&args.so_path)?)
+}
+
+/// Holds this process to the resource limits of the deployment. The hard
+/// limits are set as well so that the service cannot raise them again, though
+/// never above the hard limits this process started with since only
+/// privileged processes may raise those.
+fn limit_resources(args: &Args) -> io::Result<()> {
+    let set_limit = |resource, limit: u64| {
+        let mut rlimit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+
+        // SAFETY: only writes to the limit passed to it
+        if unsafe { libc::getrlimit(resource, &mut rlimit) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let limit = (limit as libc::rlim_t).min(rlimit.rlim_max);
+        rlimit.rlim_cur = limit;
+        rlimit.rlim_max = limit;
+
+        // SAFETY: only reads the limit passed to it
+        if unsafe { libc::setrlimit(resource, &rlimit) } == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    };
+
+    if let Some(memory_mb) = args.max_memory_mb {
+        // Counts the memory allocated rather than the address space reserved,
+        // which the runtime's threads reserve plenty of. A limit too large to
+        // count in bytes ends up as no limit at all.
+        set_limit(libc::RLIMIT_DATA, memory_mb.saturating_mul(1024 * 1024))?;
+    }
+    if let Some(open_files) = args.max_open_files {
+        set_limit(libc::RLIMIT_NOFILE, open_files)?;
+    }
+    if let Some(cgroup) = &args.cgroup {
+        wait_for_cgroup(cgroup)?;
+    }
+
+    Ok(())
+}
+
+/// Waits for the api to move this process into the cgroup holding it to its
+/// cpu limit, which it does right after starting it. This process has no
+/// business in its cgroup other than that.
+fn wait_for_cgroup(cgroup: &Path) -> io::Result<()> {
+    let pid = std::process::id().to_string();
+
+    for _ in 0..CGROUP_WAIT_TRIES {
+        let procs = fs::read_to_string(cgroup.join("cgroup.procs"))?;
+        if procs.lines().any(|line| line == pid) {
+            return Ok(());
+        }
+
+        std::thread::sleep(CGROUP_WAIT_INTERVAL);
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::TimedOut,
+        format!("api did not move this runner into cgroup {:?}", cgroup),
+    ))
+}
+
+/// Completes once the api closes the stdin of this process, which is how it
+/// asks the service to shut down. Also completes when the api goes away.
+async fn stdin_closed() {
+    let mut stdin = tokio::io::stdin();
+    let mut buf = [0; 64];
+
+    while let Ok(read) = stdin.read(&mut buf).await {
+        if read == 0 {
+            break;
+        }
+    }
+}
+
+/// Streams the logs of the service to the api as they come in.
+async fn forward_logs(mut supervisor: Supervisor, mut rx: mpsc::UnboundedReceiver<Log>) {
+    let logs = stream::poll_fn(move |cx| rx.poll_recv(cx)).filter_map(|log| async move {
+        match serde_json::to_string(&log.item) {
+            Ok(item) => Some(LogRequest {
+                deployment_id: log.deployment_id.to_string(),
+                datetime: log.datetime.to_rfc3339(),
+                item,
+            }),
+            Err(e) => {
+                warn!("failed to encode log: {}", e);
+                None
+            }
+        }
+    });
+
+    if let Err(e) = supervisor.send_logs(logs).await {
+        warn!("failed to send logs to the api: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::process::CommandExt;
+    use std::process::Command;
+
+    use clap::Parser;
+
+    use crate::args::Args;
+
+    use super::limit_resources;
+
+    /// The limits `sh` ends up with once `args` are applied to it.
+    fn applied_limits(args: &[&str]) -> String {
+        let args = Args::parse_from(
+            [
+                "shuttle-runner",
+                "--so-path",
+                "service.so",
+                "--deployment-id",
+                "6f0d6c8e-1b4e-4f0a-9d3c-4bb5b0b8a8a1",
+                "--project",
+                "limited",
+                "--port",
+                "8000",
+                "--supervisor-uri",
+                "http://localhost:8001",
+            ]
+            .iter()
+            .chain(args),
+        );
+
+        let mut command = Command::new("sh");
+        command.args(["-c", "ulimit -d; ulimit -n; ulimit -H -d; ulimit -H -n"]);
+        // SAFETY: only sets limits between forking and running `sh`
+        unsafe {
+            command.pre_exec(move || limit_resources(&args));
+        }
+
+        let output = command.output().unwrap();
+        assert!(output.status.success(), "{:?}", output);
+
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[test]
+    fn limit_resources_applies_limits() {
+        assert_eq!(
+            applied_limits(&["--max-memory-mb", "64", "--max-open-files", "128"]),
+            "65536\n128\n65536\n128\n"
+        );
+    }
+
+    #[test]
+    fn limit_resources_without_limits() {
+        let unlimited = Command::new("sh")
+            .args(["-c", "ulimit -d; ulimit -n; ulimit -H -d; ulimit -H -n"])
+            .output()
+            .unwrap()
+            .stdout;
+
+        assert_eq!(applied_limits(&[]), String::from_utf8(unlimited).unwrap());
+    }
+
+    #[test]
+    fn limit_resources_stays_under_hard_limits() {
+        let hard_open_files = Command::new("sh")
+            .args(["-c", "ulimit -H -n"])
+            .output()
+            .unwrap()
+            .stdout;
+        let hard_open_files = String::from_utf8(hard_open_files).unwrap();
+
+        // Raising a hard limit takes privileges the api should not need
+        let limits = applied_limits(&["--max-open-files", &u64::MAX.to_string()]);
+
+        assert_eq!(
+            limits.lines().nth(1),
+            hard_open_files.lines().next(),
+            "{}",
+            limits
+        );
+    }
+
+    #[test]
+    fn limit_resources_saturates() {
+        let limits = applied_limits(&["--max-memory-mb", &u64::MAX.to_string()]);
+
+        assert!(limits.starts_with("unlimited\n"), "{}", limits);
+    }
+}
@@ -0,0 +1,18 @@
+use std::env;
+use std::process::Command;
+
+fn main() {
+    // Part of the ABI version, since the layout of types crossing the FFI
+    // boundary may change from one compiler version to the next
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|version| version.trim().to_string())
+        .unwrap_or_else(|| "rustc unknown".to_string());
+
+    println!("cargo:rustc-env=SHUTTLE_RUSTC_VERSION={}", version);
+    println!("cargo:rerun-if-env-changed=RUSTC");
+}
@@ -1,7 +1,8 @@
 use crate::{
     database::{AwsRdsEngine, Type},
     error::CustomError,
-    Factory, ResourceBuilder,
+    pool::PoolConfig,
+    resource, Factory, ResourceBuilder,
 };
 use async_trait::async_trait;
 use paste::paste;
@@ -12,30 +13,55 @@ macro_rules! aws_engine {
         paste! {
             #[cfg(feature = $feature)]
             #[doc = "A resource connected to an AWS RDS " $struct_ident " instance"]
-            pub struct $struct_ident;
+            pub struct $struct_ident {
+                pool: PoolConfig,
+            }
+
+            #[cfg(feature = $feature)]
+            impl $struct_ident {
+                /// Number of connections the pool keeps open at the least, 1 by default
+                pub fn min_connections(mut self, min_connections: u32) -> Self {
+                    self.pool.min_connections = min_connections;
+                    self
+                }
+
+                /// Number of connections the pool opens at the most, 5 by default
+                pub fn max_connections(mut self, max_connections: u32) -> Self {
+                    self.pool.max_connections = max_connections;
+                    self
+                }
+
+                /// How long to wait for a connection from the pool before giving up, like `"5s"` or `"500ms"`
+                pub fn acquire_timeout(mut self, acquire_timeout: &str) -> Self {
+                    self.pool.acquire_timeout = Some(acquire_timeout.to_string());
+                    self
+                }
+            }
 
             #[cfg(feature = $feature)]
             #[doc = "Gets a `sqlx::Pool` connected to an AWS RDS " $struct_ident " instance"]
             #[async_trait]
             impl ResourceBuilder<$pool_path> for $struct_ident {
                 fn new() -> Self {
-                    Self {}
+                    Self {
+                        pool: PoolConfig::default(),
+                    }
                 }
 
                 async fn build(self, factory: &mut dyn Factory, runtime: &Runtime) -> Result<$pool_path, crate::Error> {
                     let connection_string = factory
-                        .get_sql_connection_string(Type::AwsRds(AwsRdsEngine::$struct_ident))
-                        .await?;
+                        .get_resource(resource::Request::new(
+                            resource::Type::Database(Type::AwsRds(AwsRdsEngine::$struct_ident)),
+                            None,
+                        ))
+                        .await?
+                        .connection_string_private;
+
+                    let options = self.pool.apply($options_path::new())?;
 
                     // A sqlx Pool cannot cross runtime boundaries, so make sure to create the Pool on the service end
                     let pool = runtime
-                        .spawn(async move {
-                            $options_path::new()
-                                .min_connections(1)
-                                .max_connections(5)
-                                .connect(&connection_string)
-                                .await
-                        })
+                        .spawn(async move { options.connect(&connection_string).await })
                         .await
                         .map_err(CustomError::new)?
                         .map_err(CustomError::new)?;
@@ -0,0 +1,162 @@
+//! Periodic jobs declared with [`#[shuttle_service::cron]`](macro@crate::cron) next to a service.
+//!
+//! The jobs run inside the runtime of the deployment for as long as the
+//! service itself does. Every run is reported through the [`Logger`], both as
+//! a readable log and with the fields the api reads the last and next run of
+//! a job from.
+
+use std::future::Future;
+use std::net::SocketAddr;
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::future::{self, Either, FutureExt};
+use log::Level;
+use shuttle_common::{CronJobMeta, LogItem, CRON_LOG_TARGET};
+
+use crate::logger::Logger;
+use crate::{Error, Service, Shutdown};
+
+pub use shuttle_common::cron::{Schedule, ScheduleError};
+
+/// The future of a single run of a job.
+pub type JobFuture = Pin<Box<dyn Future<Output = Result<(), Error>> + Send>>;
+
+/// A job as registered by `#[shuttle_service::cron]`.
+#[doc(hidden)]
+pub struct Job {
+    pub name: &'static str,
+    pub schedule: &'static str,
+    pub run: fn() -> JobFuture,
+}
+
+inventory::collect!(Job);
+
+/// Wraps the service of a deployment to run its jobs alongside it. The jobs
+/// stop when the service does.
+pub struct Scheduled {
+    service: Box<dyn Service>,
+    logger: Logger,
+}
+
+impl Scheduled {
+    pub fn new(service: Box<dyn Service>, logger: Logger) -> Self {
+        Self { service, logger }
+    }
+}
+
+#[async_trait]
+impl Service for Scheduled {
+    async fn bind(self: Box<Self>, addr: SocketAddr) -> Result<(), Error> {
+        let scheduler = scheduler(self.logger.clone());
+
+        run_alongside(self.service.bind(addr), scheduler).await
+    }
+
+    async fn bind_with_shutdown(
+        self: Box<Self>,
+        addr: SocketAddr,
+        shutdown: Shutdown,
+    ) -> Result<(), Error> {
+        let scheduler = scheduler(self.logger.clone());
+
+        run_alongside(self.service.bind_with_shutdown(addr, shutdown), scheduler).await
+    }
+
+    fn is_routable(&self) -> bool {
+        self.service.is_routable()
+    }
+}
+
+/// Runs all the jobs of the service. Never finishes, even once no job will
+/// ever run again, so that it does not stop the service.
+fn scheduler(logger: Logger) -> impl Future<Output = ()> + Send {
+    let jobs: Vec<_> = inventory::iter::<Job>
+        .into_iter()
+        .map(|job| run(job, logger.clone()))
+        .collect();
+
+    future::join_all(jobs).then(|_| future::pending())
+}
+
+async fn run_alongside(
+    service: impl Future<Output = Result<(), Error>> + Unpin,
+    scheduler: impl Future<Output = ()>,
+) -> Result<(), Error> {
+    match future::select(service, Box::pin(scheduler)).await {
+        Either::Left((result, _)) => result,
+        Either::Right(_) => unreachable!("the scheduler never finishes"),
+    }
+}
+
+/// Runs a job whenever its schedule says so, one run after the other.
+async fn run(job: &'static Job, logger: Logger) {
+    let schedule = match job.schedule.parse::<Schedule>() {
+        Ok(schedule) => schedule,
+        Err(e) => {
+            report(&logger, job, None, None, Some(e.to_string()));
+            return;
+        }
+    };
+
+    let mut next_run = schedule.next_after(Utc::now());
+    report(&logger, job, None, next_run, None);
+
+    while let Some(at) = next_run {
+        if let Ok(wait) = (at - Utc::now()).to_std() {
+            tokio::time::sleep(wait).await;
+        }
+
+        let started = Utc::now();
+        let error = match AssertUnwindSafe((job.run)()).catch_unwind().await {
+            Ok(Ok(())) => None,
+            Ok(Err(e)) => Some(e.to_string()),
+            Err(panic) => Some(
+                panic
+                    .downcast_ref::<&str>()
+                    .map(|x| x.to_string())
+                    .unwrap_or_else(|| "<no panic message>".to_string()),
+            ),
+        };
+
+        next_run = schedule.next_after(Utc::now());
+        report(&logger, job, Some(started), next_run, error);
+    }
+}
+
+/// Sends the state of a job to the api. This does not go through the level
+/// filter of the logger, since the api relies on getting it.
+fn report(
+    logger: &Logger,
+    job: &Job,
+    last_run: Option<DateTime<Utc>>,
+    next_run: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+) {
+    let (level, body) = match (&last_error, last_run) {
+        (Some(e), _) => (
+            Level::Error,
+            format!("cron job `{}` failed: {}", job.name, e),
+        ),
+        (None, Some(_)) => (Level::Info, format!("cron job `{}` finished", job.name)),
+        (None, None) => (Level::Info, format!("cron job `{}` scheduled", job.name)),
+    };
+
+    let meta = CronJobMeta {
+        name: job.name.to_string(),
+        schedule: job.schedule.to_string(),
+        last_run,
+        next_run,
+        last_error,
+    };
+
+    logger.send(LogItem {
+        body,
+        level,
+        target: CRON_LOG_TARGET.to_string(),
+        fields: meta.to_fields(),
+        spans: Vec::new(),
+    });
+}
@@ -207,22 +207,35 @@ use std::future::Future;
 use std::net::SocketAddr;
 use std::pin::Pin;
 
+use futures::future::{self, Either};
+
 pub use async_trait::async_trait;
 
 // Pub uses by `codegen`
+#[doc(hidden)]
+pub use inventory;
 pub use log;
 pub use tokio::runtime::Runtime;
 
+pub mod cron;
+
 pub mod error;
 pub use error::Error;
 
 pub mod logger;
 
-pub use shuttle_common::database;
+pub use shuttle_common::{database, resource};
 
-#[cfg(feature = "sqlx-postgres")]
+#[cfg(any(
+    feature = "sqlx-postgres",
+    feature = "cache-redis",
+    feature = "mongodb-integration"
+))]
 pub mod shared;
 
+#[cfg(feature = "sqlx-integration")]
+mod pool;
+
 #[cfg(feature = "secrets")]
 pub mod secrets;
 #[cfg(feature = "secrets")]
@@ -262,8 +275,14 @@ extern crate shuttle_codegen;
 /// | ------------------------------------- | ------------ | ------------------------------------------- | ---------- | ----------------------------------------------------------------------------------- |
 /// | `ShuttleRocket`                       | web-rocket   | [rocket](https://docs.rs/rocket/0.5.0-rc.2) | 0.5.0-rc.2 | [GitHub](https://github.com/getsynth/shuttle/tree/main/examples/rocket/hello-world) |
 /// | `ShuttleAxum`                         | web-axum     | [axum](https://docs.rs/axum/0.5)            | 0.5        | [GitHub](https://github.com/getsynth/shuttle/tree/main/examples/axum/hello-world)   |
+/// | `ShuttleActixWeb<F>`                  | web-actix-web | [actix-web](https://docs.rs/actix-web/4.1)  | 4.1        | [GitHub](https://github.com/getsynth/shuttle/tree/main/examples/actix-web/hello-world) |
 /// | `ShuttleTide`                         | web-tide     | [tide](https://docs.rs/tide/0.16.0)         | 0.16.0     | [GitHub](https://github.com/getsynth/shuttle/tree/main/examples/tide/hello-world)   |
 /// | `ShuttlePoem`                         | web-poem     | [poem](https://docs.rs/poem/1.3.35)         | 1.3.35     | [GitHub](https://github.com/getsynth/shuttle/tree/main/examples/poem/hello-world)   |
+/// | `ShuttleWarp<T>`                      | web-warp     | [warp](https://docs.rs/warp/0.3.2)          | 0.3.2      | [GitHub](https://github.com/getsynth/shuttle/tree/main/examples/warp/hello-world)   |
+/// | `ShuttleSalvo`                        | web-salvo    | [salvo](https://docs.rs/salvo/0.27.0)       | 0.27.0     | [GitHub](https://github.com/getsynth/shuttle/tree/main/examples/salvo/hello-world)  |
+/// | `ShuttleTonic`                        | grpc-tonic   | [tonic](https://docs.rs/tonic/0.8.0)        | 0.8.0      |                                                                                     |
+/// | `ShuttleTcp`                          |              | Raw TCP, see [TcpService]                   |            |                                                                                     |
+/// | `ShuttleWorker`                       |              | No requests, see [WorkerService]            |            |                                                                                     |
 /// | `Result<T, shuttle_service::Error>`   | web-tower    | [tower](https://docs.rs/tower/0.4.12)       | 0.14.12    | [GitHub](https://github.com/getsynth/shuttle/tree/main/examples/tower/hello-world)  |
 ///
 /// # Getting shuttle managed services
@@ -292,7 +311,73 @@ extern crate shuttle_codegen;
 /// | [`MySqlPool`](https://docs.rs/sqlx/latest/sqlx/type.MySqlPool.html) | sqlx-aws-mariadb  | `aws::rds::MariaDB`  | An AWS RDS MariaDB instance tied to your instance and accessed using [sqlx](https://docs.rs/sqlx)  |                                                                                  |
 /// | [`MySqlPool`](https://docs.rs/sqlx/latest/sqlx/type.MySqlPool.html) | sqlx-aws-mysql    | `aws::rds::MySql`    | An AWS RDS MySql instance tied to your instance and accessed using [sqlx](https://docs.rs/sqlx)    |                                                                                  |
 /// | [`PgPool`](https://docs.rs/sqlx/latest/sqlx/type.PgPool.html)       | sqlx-aws-postgres | `aws::rds::Postgres` | An AWS RDS Postgres instance tied to your instance and accessed using [sqlx](https://docs.rs/sqlx) | [GitHub](https://github.com/getsynth/shuttle/tree/main/examples/tide/postgres)   |
+/// | [`ConnectionManager`](https://docs.rs/redis/latest/redis/aio/struct.ConnectionManager.html) | cache-redis       | `shared::Redis`      | A shared Redis instance accessed using [redis](https://docs.rs/redis)                              |                                                                                  |
+/// | [`Database`](https://docs.rs/mongodb/latest/mongodb/struct.Database.html) | mongodb-integration | `shared::MongoDb`    | A database on a shared MongoDB instance accessed using [mongodb](https://docs.rs/mongodb)          |                                                                                  |
+///
+/// ## Several resources of the same type
+/// Shared resources can be given a name to get more than one of them, like a database for analytics next to the
+/// database of the app. Names are lowercase letters and digits, starting with a letter:
+/// ```rust,no_run
+/// use sqlx::PgPool;
+/// use shuttle_service::ShuttleRocket;
+///
+/// struct MyState {
+///     pool: PgPool,
+///     analytics: PgPool,
+/// }
+///
+/// #[shuttle_service::main]
+/// async fn rocket(
+///     #[shared::Postgres] pool: PgPool,
+///     #[shared::Postgres(name = "analytics")] analytics: PgPool,
+/// ) -> ShuttleRocket {
+///     let state = MyState { pool, analytics };
+///     let rocket = rocket::build().manage(state);
+///
+///     Ok(rocket)
+/// }
+/// ```
+///
+/// ## Connection pool options
+/// The sqlx resources take the options of their connection pool on the attribute: `min_connections` (1 by default),
+/// `max_connections` (5 by default) and `acquire_timeout`, which is how long to wait for a connection from the pool,
+/// written like `"500ms"`, `"5s"` or `"2m"`:
+/// ```rust,no_run
+/// use sqlx::PgPool;
+/// use shuttle_service::ShuttleRocket;
+///
+/// struct MyState(PgPool);
+///
+/// #[shuttle_service::main]
+/// async fn rocket(
+///     #[shared::Postgres(max_connections = 20, acquire_timeout = "5s")] pool: PgPool,
+/// ) -> ShuttleRocket {
+///     let state = MyState(pool);
+///     let rocket = rocket::build().manage(state);
+///
+///     Ok(rocket)
+/// }
+/// ```
 pub use shuttle_codegen::main;
+
+#[cfg(feature = "codegen")]
+/// Declares an async function as a job which runs periodically alongside the service, inside the same runtime.
+/// The schedule takes the usual five fields of cron, in UTC, see [cron::Schedule]. The function takes no arguments and
+/// returns `Result<(), shuttle_service::Error>`.
+///
+/// ```rust,no_run
+/// #[shuttle_service::cron("0 * * * *")]
+/// async fn cleanup() -> Result<(), shuttle_service::Error> {
+///     log::info!("cleaning up");
+///
+///     Ok(())
+/// }
+/// ```
+///
+/// Every run is logged with the `shuttle_service::cron` target, and the deployment shows when each job last ran and
+/// when it runs next. A failed or panicking run is logged as an error and the job runs again on its next turn.
+pub use shuttle_codegen::cron;
+use tokio::net::TcpListener;
 use tokio::task::JoinHandle;
 
 #[cfg(feature = "loader")]
@@ -305,13 +390,14 @@ pub mod loader;
 /// Also see the [main][main] macro.
 #[async_trait]
 pub trait Factory: Send + Sync {
-    /// Declare that the [Service][Service] requires a Postgres database.
+    /// Declare that the [Service][Service] requires a resource.
     ///
-    /// Returns the connection string to the provisioned database.
-    async fn get_sql_connection_string(
+    /// Returns how to reach the provisioned resource. Asking for the same type of resource under
+    /// the same name more than once gives the same resource each time.
+    async fn get_resource(
         &mut self,
-        db_type: database::Type,
-    ) -> Result<String, crate::Error>;
+        request: resource::Request,
+    ) -> Result<resource::Response, crate::Error>;
 }
 
 /// Used to get resources of type `T` from factories.
@@ -328,6 +414,10 @@ pub trait ResourceBuilder<T> {
 /// A tokio handle the service was started on
 pub type ServeHandle = JoinHandle<Result<(), error::Error>>;
 
+/// Completes when a service should stop taking new requests, like when it is replaced by a new deployment or
+/// deleted.
+pub type Shutdown = Pin<Box<dyn Future<Output = ()> + Send>>;
+
 /// The core trait of the shuttle platform. Every crate deployed to shuttle needs to implement this trait.
 ///
 /// Use the [main][main] macro to expose your implementation to the deployment backend.
@@ -340,21 +430,42 @@ pub trait Service: Send + Sync {
     ///
     /// The deployer expects this instance of [Service][Service] to bind to the passed [SocketAddr][SocketAddr].
     async fn bind(mut self: Box<Self>, addr: SocketAddr) -> Result<(), error::Error>;
+
+    /// Like [bind][Service::bind], but once `shutdown` completes the service stops taking new requests and returns
+    /// when the requests in flight are done. The deployer aborts services which take too long to do so.
+    ///
+    /// The default implementation cannot drain requests, so it stops the service right away.
+    async fn bind_with_shutdown(
+        mut self: Box<Self>,
+        addr: SocketAddr,
+        shutdown: Shutdown,
+    ) -> Result<(), error::Error> {
+        match future::select(self.bind(addr), shutdown).await {
+            Either::Left((result, _)) => result,
+            Either::Right(((), _)) => Ok(()),
+        }
+    }
+
+    /// Whether the service takes requests on the address it binds to. Services which do not, like
+    /// background workers, are kept running without any requests being routed to them.
+    fn is_routable(&self) -> bool {
+        true
+    }
 }
 
 /// This function is generated by our codegen. It uses the factory to get other services and instantiate them on
-/// the correct tokio runtime. This function also sets the runtime logger. The output is a future where `T`
-/// should implement [Service].
+/// the correct tokio runtime. This function also installs the runtime [Logger][logger::Logger]. The output is a
+/// future where `T` should implement [Service].
 pub type StateBuilder<T> =
     for<'a> fn(
         &'a mut dyn Factory,
         &'a Runtime,
-        Box<dyn log::Log>,
+        logger::Logger,
     ) -> Pin<Box<dyn Future<Output = Result<T, Error>> + Send + 'a>>;
 
 /// This function is generated by codegen to ensure binding happens on the other side of the FFI and on the correct
 /// tokio runtime.
-pub type Binder = for<'a> fn(Box<dyn Service>, SocketAddr, &'a Runtime) -> ServeHandle;
+pub type Binder = for<'a> fn(Box<dyn Service>, SocketAddr, Shutdown, &'a Runtime) -> ServeHandle;
 
 #[allow(dead_code)]
 pub struct Bootstrapper {
@@ -380,7 +491,7 @@ impl Bootstrapper {
     async fn bootstrap(
         &mut self,
         factory: &mut dyn Factory,
-        logger: Box<dyn log::Log>,
+        logger: logger::Logger,
     ) -> Result<(), Error> {
         if let Some(builder) = self.builder.take() {
             let service = builder(factory, self.runtime.as_ref().unwrap(), logger).await?;
@@ -391,10 +502,17 @@ impl Bootstrapper {
     }
 
     #[cfg(feature = "loader")]
-    fn into_handle(mut self, addr: SocketAddr) -> Result<ServeHandle, Error> {
+    fn is_routable(&self) -> bool {
+        self.service
+            .as_ref()
+            .map_or(true, |service| service.is_routable())
+    }
+
+    #[cfg(feature = "loader")]
+    fn into_handle(mut self, addr: SocketAddr, shutdown: Shutdown) -> Result<ServeHandle, Error> {
         let service = self.service.take().expect("service has already been bound");
 
-        let handle = (self.binder)(service, addr, self.runtime.as_ref().unwrap());
+        let handle = (self.binder)(service, addr, shutdown, self.runtime.as_ref().unwrap());
 
         Ok(handle)
     }
@@ -413,26 +531,55 @@ impl Drop for Bootstrapper {
 #[async_trait]
 impl Service for rocket::Rocket<rocket::Build> {
     async fn bind(mut self: Box<Self>, addr: SocketAddr) -> Result<(), error::Error> {
-        let shutdown = rocket::config::Shutdown {
-            ctrlc: false,
-            ..rocket::config::Shutdown::default()
-        };
-
-        let config = rocket::Config {
-            address: addr.ip(),
-            port: addr.port(),
-            log_level: rocket::config::LogLevel::Off,
-            shutdown,
-            ..Default::default()
-        };
         let _rocket = self
-            .configure(config)
+            .configure(rocket_config(addr))
             .launch()
             .await
             .map_err(error::CustomError::new)?;
 
         Ok(())
     }
+
+    async fn bind_with_shutdown(
+        mut self: Box<Self>,
+        addr: SocketAddr,
+        shutdown: Shutdown,
+    ) -> Result<(), error::Error> {
+        let rocket = self
+            .configure(rocket_config(addr))
+            .ignite()
+            .await
+            .map_err(error::CustomError::new)?;
+
+        // Rocket drains its connections within the grace periods of its `Shutdown` config
+        let handle = rocket.shutdown();
+        let notify = tokio::spawn(async move {
+            shutdown.await;
+            handle.notify();
+        });
+
+        let launched = rocket.launch().await;
+        notify.abort();
+        let _rocket = launched.map_err(error::CustomError::new)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "web-rocket")]
+fn rocket_config(addr: SocketAddr) -> rocket::Config {
+    let shutdown = rocket::config::Shutdown {
+        ctrlc: false,
+        ..rocket::config::Shutdown::default()
+    };
+
+    rocket::Config {
+        address: addr.ip(),
+        port: addr.port(),
+        log_level: rocket::config::LogLevel::Off,
+        shutdown,
+        ..Default::default()
+    }
 }
 
 #[cfg(feature = "web-rocket")]
@@ -452,6 +599,19 @@ where
 
         Ok(())
     }
+
+    async fn bind_with_shutdown(
+        mut self: Box<Self>,
+        addr: SocketAddr,
+        shutdown: Shutdown,
+    ) -> Result<(), error::Error> {
+        poem::Server::new(poem::listener::TcpListener::bind(addr))
+            .run_with_graceful_shutdown(self, shutdown, None)
+            .await
+            .map_err(error::CustomError::new)?;
+
+        Ok(())
+    }
 }
 
 #[cfg(feature = "web-poem")]
@@ -470,11 +630,93 @@ impl Service for sync_wrapper::SyncWrapper<axum::Router> {
 
         Ok(())
     }
+
+    async fn bind_with_shutdown(
+        mut self: Box<Self>,
+        addr: SocketAddr,
+        shutdown: Shutdown,
+    ) -> Result<(), error::Error> {
+        let router = self.into_inner();
+
+        axum::Server::bind(&addr)
+            .serve(router.into_make_service())
+            .with_graceful_shutdown(shutdown)
+            .await
+            .map_err(error::CustomError::new)?;
+
+        Ok(())
+    }
 }
 
 #[cfg(feature = "web-axum")]
 pub type ShuttleAxum = Result<sync_wrapper::SyncWrapper<axum::Router>, Error>;
 
+/// An actix-web service, made up of the configuration every worker builds its `App` from.
+#[cfg(feature = "web-actix-web")]
+pub struct ActixWebService<F>(pub F);
+
+#[cfg(feature = "web-actix-web")]
+impl<F> From<F> for ActixWebService<F>
+where
+    F: FnOnce(&mut actix_web::web::ServiceConfig) + Sync + Send + Clone + 'static,
+{
+    fn from(config: F) -> Self {
+        Self(config)
+    }
+}
+
+#[cfg(feature = "web-actix-web")]
+#[async_trait]
+impl<F> Service for ActixWebService<F>
+where
+    F: FnOnce(&mut actix_web::web::ServiceConfig) + Sync + Send + Clone + 'static,
+{
+    async fn bind(mut self: Box<Self>, addr: SocketAddr) -> Result<(), error::Error> {
+        let config = self.0;
+
+        // `actix_web::App` is not `Send`, so every worker builds its own from the service's config.
+        // Neither is `HttpServer`, so it must be gone before awaiting the server it runs.
+        let server =
+            actix_web::HttpServer::new(move || actix_web::App::new().configure(config.clone()))
+                .bind(addr)?
+                .run();
+        server.await.map_err(error::CustomError::new)?;
+
+        Ok(())
+    }
+
+    async fn bind_with_shutdown(
+        mut self: Box<Self>,
+        addr: SocketAddr,
+        shutdown: Shutdown,
+    ) -> Result<(), error::Error> {
+        let config = self.0;
+        let server =
+            actix_web::HttpServer::new(move || actix_web::App::new().configure(config.clone()))
+                .bind(addr)?
+                .run();
+        let handle = server.handle();
+
+        let server = match future::select(Box::pin(server), shutdown).await {
+            Either::Left((result, _)) => {
+                result.map_err(error::CustomError::new)?;
+
+                return Ok(());
+            }
+            Either::Right(((), server)) => server,
+        };
+
+        // Stopping gracefully lets the workers finish the requests they are on
+        handle.stop(true).await;
+        server.await.map_err(error::CustomError::new)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "web-actix-web")]
+pub type ShuttleActixWeb<F> = Result<ActixWebService<F>, Error>;
+
 #[cfg(feature = "web-tide")]
 #[async_trait]
 impl<T> Service for tide::Server<T>
@@ -491,6 +733,63 @@ where
 #[cfg(feature = "web-tide")]
 pub type ShuttleTide<T> = Result<tide::Server<T>, Error>;
 
+#[cfg(feature = "web-warp")]
+#[async_trait]
+impl<T> Service for T
+where
+    T: warp::Filter + Clone + Send + Sync + 'static,
+    T::Extract: warp::reply::Reply,
+{
+    async fn bind(mut self: Box<Self>, addr: SocketAddr) -> Result<(), error::Error> {
+        warp::serve(*self).run(addr).await;
+
+        Ok(())
+    }
+
+    async fn bind_with_shutdown(
+        mut self: Box<Self>,
+        addr: SocketAddr,
+        shutdown: Shutdown,
+    ) -> Result<(), error::Error> {
+        let (_, server) = warp::serve(*self)
+            .try_bind_with_graceful_shutdown(addr, shutdown)
+            .map_err(error::CustomError::new)?;
+        server.await;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "web-warp")]
+pub type ShuttleWarp<T> = Result<warp::filters::BoxedFilter<T>, Error>;
+
+#[cfg(feature = "web-salvo")]
+#[async_trait]
+impl Service for salvo::Router {
+    async fn bind(mut self: Box<Self>, addr: SocketAddr) -> Result<(), error::Error> {
+        salvo::Server::new(salvo::listener::TcpListener::bind(addr))
+            .serve(*self)
+            .await;
+
+        Ok(())
+    }
+
+    async fn bind_with_shutdown(
+        mut self: Box<Self>,
+        addr: SocketAddr,
+        shutdown: Shutdown,
+    ) -> Result<(), error::Error> {
+        salvo::Server::new(salvo::listener::TcpListener::bind(addr))
+            .serve_with_graceful_shutdown(*self, shutdown)
+            .await;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "web-salvo")]
+pub type ShuttleSalvo = Result<salvo::Router, Error>;
+
 #[cfg(feature = "web-tower")]
 #[async_trait]
 impl<T> Service for T
@@ -512,6 +811,190 @@ where
 
         Ok(())
     }
+
+    async fn bind_with_shutdown(
+        mut self: Box<Self>,
+        addr: SocketAddr,
+        shutdown: Shutdown,
+    ) -> Result<(), error::Error> {
+        let shared = tower::make::Shared::new(self);
+        hyper::Server::bind(&addr)
+            .serve(shared)
+            .with_graceful_shutdown(shutdown)
+            .await
+            .map_err(error::CustomError::new)?;
+
+        Ok(())
+    }
+}
+
+/// A tonic gRPC service. Its router is not `Sync`, so it is kept wrapped until the service gets bound.
+#[cfg(feature = "grpc-tonic")]
+pub struct TonicService(sync_wrapper::SyncWrapper<tonic::transport::server::Router>);
+
+#[cfg(feature = "grpc-tonic")]
+impl From<tonic::transport::server::Router> for TonicService {
+    fn from(router: tonic::transport::server::Router) -> Self {
+        Self(sync_wrapper::SyncWrapper::new(router))
+    }
 }
 
+#[cfg(feature = "grpc-tonic")]
+#[async_trait]
+impl Service for TonicService {
+    async fn bind(mut self: Box<Self>, addr: SocketAddr) -> Result<(), error::Error> {
+        let router = self.0.into_inner();
+
+        router.serve(addr).await.map_err(error::CustomError::new)?;
+
+        Ok(())
+    }
+
+    async fn bind_with_shutdown(
+        mut self: Box<Self>,
+        addr: SocketAddr,
+        shutdown: Shutdown,
+    ) -> Result<(), error::Error> {
+        let router = self.0.into_inner();
+
+        router
+            .serve_with_shutdown(addr, shutdown)
+            .await
+            .map_err(error::CustomError::new)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "grpc-tonic")]
+pub type ShuttleTonic = Result<TonicService, Error>;
+
+/// A service speaking a protocol other than HTTP directly over TCP, like a game or protocol
+/// server. It gets handed the listener bound to the address picked by the deployer.
+///
+/// Set `protocol = "tcp"` in `Shuttle.toml` for the service to be reachable on a public port of
+/// its own, since shuttle routes HTTP requests by their `Host` header otherwise.
+///
+/// The deployer checks on the service every few seconds, and while it gets ready, by opening a
+/// connection to it and closing it again without sending anything. Those connections look like
+/// any other client to the service, so it should not count on clients to send something or to
+/// stay around, and should not stop when a single connection fails.
+///
+/// ```rust,no_run
+/// use shuttle_service::{ShuttleTcp, TcpService};
+/// use tokio::io::AsyncWriteExt;
+/// use tokio::net::TcpListener;
+///
+/// async fn greet(listener: TcpListener) -> Result<(), shuttle_service::Error> {
+///     loop {
+///         let (mut socket, _) = listener.accept().await?;
+///
+///         // The client may be gone already, like the deployer checking on the service
+///         let _ = socket.write_all(b"Hello, world!\n").await;
+///     }
+/// }
+///
+/// #[shuttle_service::main]
+/// async fn tcp() -> ShuttleTcp {
+///     Ok(TcpService::new(greet))
+/// }
+/// ```
+pub struct TcpService {
+    serve: ServeTcp,
+}
+
+type ServeTcp = Box<
+    dyn FnOnce(TcpListener) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send>>
+        + Send
+        + Sync,
+>;
+
+impl TcpService {
+    /// Creates a service which runs `serve` with the listener once it is bound.
+    pub fn new<F, Fut>(serve: F) -> Self
+    where
+        F: FnOnce(TcpListener) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), Error>> + Send + 'static,
+    {
+        Self {
+            serve: Box::new(move |listener| Box::pin(serve(listener))),
+        }
+    }
+}
+
+#[async_trait]
+impl Service for TcpService {
+    async fn bind(mut self: Box<Self>, addr: SocketAddr) -> Result<(), error::Error> {
+        let listener = TcpListener::bind(addr).await?;
+
+        (self.serve)(listener).await
+    }
+}
+
+pub type ShuttleTcp = Result<TcpService, Error>;
+
+/// A service doing its work in the background, like a queue consumer or a scheduler, instead of
+/// taking requests. It never binds to the address it is given and no requests are routed to it.
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+///
+/// use shuttle_service::{ShuttleWorker, WorkerService};
+///
+/// #[shuttle_service::main]
+/// async fn worker() -> ShuttleWorker {
+///     Ok(WorkerService::new(|| async {
+///         loop {
+///             log::info!("doing some work");
+///             tokio::time::sleep(Duration::from_secs(60)).await;
+///         }
+///     }))
+/// }
+/// ```
+pub struct WorkerService {
+    work: Work,
+}
+
+type Work =
+    Box<dyn FnOnce() -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send>> + Send + Sync>;
+
+impl WorkerService {
+    /// Creates a service which runs `work` once it gets started.
+    pub fn new<F, Fut>(work: F) -> Self
+    where
+        F: FnOnce() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), Error>> + Send + 'static,
+    {
+        Self {
+            work: Box::new(move || Box::pin(work())),
+        }
+    }
+}
+
+#[async_trait]
+impl Service for WorkerService {
+    async fn bind(mut self: Box<Self>, _addr: SocketAddr) -> Result<(), error::Error> {
+        (self.work)().await
+    }
+
+    fn is_routable(&self) -> bool {
+        false
+    }
+}
+
+pub type ShuttleWorker = Result<WorkerService, Error>;
+
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Versions of this crate and of the compiler it was built with. A service and the loader loading it
+/// have to agree on both since the layout of [Bootstrapper] depends on them. Exported by the [main]
+/// macro and checked by the loader before the service is created. Ends with a nul byte so it can be
+/// passed across the FFI boundary as a C string.
+#[doc(hidden)]
+pub const ABI_VERSION: &str = concat!(
+    "shuttle-service ",
+    env!("CARGO_PKG_VERSION"),
+    ", ",
+    env!("SHUTTLE_RUSTC_VERSION"),
+    "\0"
+);
@@ -1,6 +1,7 @@
 use std::any::Any;
-use std::ffi::OsStr;
+use std::ffi::{CStr, OsStr};
 use std::net::SocketAddr;
+use std::os::raw::c_char;
 use std::panic::AssertUnwindSafe;
 use std::path::{Path, PathBuf};
 
@@ -11,23 +12,25 @@ use cargo::ops::{compile, CompileOptions};
 use cargo::util::homedir;
 use cargo::Config;
 use libloading::{Library, Symbol};
-use log::trace;
+use log::{trace, LevelFilter};
 use shuttle_common::DeploymentId;
 use thiserror::Error as ThisError;
 use tokio::sync::mpsc::UnboundedSender;
 
-use futures::FutureExt;
+use futures::{Future, FutureExt};
 
 use crate::error::CustomError;
-use crate::Bootstrapper;
 use crate::{
     logger::{Log, Logger},
     Error, Factory, ServeHandle,
 };
+use crate::{Bootstrapper, ABI_VERSION};
 
 const ENTRYPOINT_SYMBOL_NAME: &[u8] = b"_create_service\0";
+const ABI_VERSION_SYMBOL_NAME: &[u8] = b"_shuttle_abi_version\0";
 
 type CreateService = unsafe extern "C" fn() -> *mut Bootstrapper;
+type AbiVersion = unsafe extern "C" fn() -> *const c_char;
 
 #[derive(Debug, ThisError)]
 pub enum LoaderError {
@@ -35,6 +38,8 @@ pub enum LoaderError {
     Load(libloading::Error),
     #[error("failed to find the shuttle entrypoint. Did you use the provided shuttle macros?")]
     GetEntrypoint(libloading::Error),
+    #[error("the service was built with {found}, but this loader was built with {expected}. Please build the service with the same versions")]
+    IncompatibleAbi { found: String, expected: String },
 }
 
 pub struct Loader {
@@ -55,6 +60,15 @@ impl Loader {
             let entrypoint: Symbol<CreateService> = lib
                 .get(ENTRYPOINT_SYMBOL_NAME)
                 .map_err(LoaderError::GetEntrypoint)?;
+
+            // The layout of the bootstrapper is only known to match when both
+            // sides were built the same way
+            let found = match lib.get::<AbiVersion>(ABI_VERSION_SYMBOL_NAME) {
+                Ok(abi_version) => CStr::from_ptr(abi_version()).to_string_lossy().into_owned(),
+                Err(_) => "an older shuttle-service which does not report its version".to_string(),
+            };
+            check_abi_version(found)?;
+
             let raw = entrypoint();
 
             Ok(Self {
@@ -64,15 +78,22 @@ impl Loader {
         }
     }
 
+    /// Builds the service and starts it on `addr`. Along with the handle of the running service,
+    /// returns whether it takes requests, see [`Service::is_routable`](crate::Service::is_routable).
+    ///
+    /// The service shuts down gracefully once `shutdown` completes, see
+    /// [`Service::bind_with_shutdown`](crate::Service::bind_with_shutdown).
     pub async fn load(
         self,
         factory: &mut dyn Factory,
         addr: SocketAddr,
+        shutdown: impl Future<Output = ()> + Send + 'static,
         tx: UnboundedSender<Log>,
         deployment_id: DeploymentId,
-    ) -> Result<(ServeHandle, Library), Error> {
+        log_level: LevelFilter,
+    ) -> Result<(ServeHandle, Library, bool), Error> {
         let mut bootstrapper = self.bootstrapper;
-        let logger = Box::new(Logger::new(tx, deployment_id));
+        let logger = Logger::new(tx, deployment_id, log_level);
 
         AssertUnwindSafe(bootstrapper.bootstrap(factory, logger))
             .catch_unwind()
@@ -81,22 +102,27 @@ impl Loader {
 
         trace!("bootstrapping done");
 
+        let routable = bootstrapper.is_routable();
+
         // Start service on this side of the FFI
         let handle = tokio::spawn(async move {
-            bootstrapper.into_handle(addr)?.await.map_err(|e| {
-                if e.is_panic() {
-                    let mes = e.into_panic();
-
-                    Error::BindPanic(map_any_to_panic_string(mes))
-                } else {
-                    Error::Custom(CustomError::new(e))
-                }
-            })?
+            bootstrapper
+                .into_handle(addr, Box::pin(shutdown))?
+                .await
+                .map_err(|e| {
+                    if e.is_panic() {
+                        let mes = e.into_panic();
+
+                        Error::BindPanic(map_any_to_panic_string(mes))
+                    } else {
+                        Error::Custom(CustomError::new(e))
+                    }
+                })?
         });
 
         trace!("creating handle done");
 
-        Ok((handle, self.so))
+        Ok((handle, self.so, routable))
     }
 }
 
@@ -158,6 +184,21 @@ pub fn build_crate(project_path: &Path, buf: Box<dyn std::io::Write>) -> anyhow:
     Ok(compilation.cdylibs[0].path.clone())
 }
 
+/// Checks that a service was built with the same shuttle-service and compiler
+/// versions as this loader, see [`ABI_VERSION`].
+fn check_abi_version(found: String) -> Result<(), LoaderError> {
+    let expected = ABI_VERSION.trim_end_matches('\0');
+
+    if found == expected {
+        Ok(())
+    } else {
+        Err(LoaderError::IncompatibleAbi {
+            found,
+            expected: expected.to_string(),
+        })
+    }
+}
+
 fn map_any_to_panic_string(a: Box<dyn Any>) -> String {
     a.downcast_ref::<&str>()
         .map(|x| x.to_string())
@@ -176,4 +217,26 @@ mod tests {
             assert!(matches!(result, Err(LoaderError::Load(_))));
         }
     }
+
+    mod check_abi_version {
+        use crate::loader::{check_abi_version, LoaderError};
+        use crate::ABI_VERSION;
+
+        #[test]
+        fn same() {
+            let found = ABI_VERSION.trim_end_matches('\0').to_string();
+
+            assert!(check_abi_version(found).is_ok());
+        }
+
+        #[test]
+        fn different() {
+            let result = check_abi_version("shuttle-service 0.1.0, rustc 1.0.0".to_string());
+
+            assert!(matches!(
+                result,
+                Err(LoaderError::IncompatibleAbi { found, .. }) if found == "shuttle-service 0.1.0, rustc 1.0.0"
+            ));
+        }
+    }
 }
@@ -1,7 +1,19 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+use anyhow::anyhow;
 use chrono::{DateTime, Utc};
-use log::{Level, Metadata, Record};
-use shuttle_common::{DeploymentId, LogItem};
+use log::{LevelFilter, Metadata, Record};
+use shuttle_common::{DeploymentId, LogItem, LogSpan};
 use tokio::sync::mpsc::UnboundedSender;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{Layer, Registry};
+
+use crate::Error;
 
 #[derive(Debug)]
 pub struct Log {
@@ -10,40 +22,239 @@ pub struct Log {
     pub item: LogItem,
 }
 
+/// Forwards the logs of a service, from both the `log` and `tracing` crates,
+/// to the api.
+#[derive(Clone)]
 pub struct Logger {
     deployment_id: DeploymentId,
     tx: UnboundedSender<Log>,
+    level: LevelFilter,
 }
 
 impl Logger {
-    pub fn new(tx: UnboundedSender<Log>, deployment_id: DeploymentId) -> Self {
-        Self { tx, deployment_id }
+    pub fn new(tx: UnboundedSender<Log>, deployment_id: DeploymentId, level: LevelFilter) -> Self {
+        Self {
+            tx,
+            deployment_id,
+            level,
+        }
+    }
+
+    /// Makes this the global `log` logger and `tracing` subscriber. Has to be
+    /// called on the service's side of the FFI boundary, since the service
+    /// has its own copy of the globals of both crates.
+    pub fn install(self) -> Result<(), Error> {
+        log::set_boxed_logger(Box::new(self.clone()))
+            .map_err(|e| anyhow!("failed to set logger: {}", e))?;
+        log::set_max_level(self.level);
+
+        tracing::subscriber::set_global_default(Registry::default().with(self))
+            .map_err(|e| anyhow!("failed to set tracing subscriber: {}", e))?;
+
+        Ok(())
+    }
+
+    pub(crate) fn send(&self, item: LogItem) {
+        self.tx
+            .send(Log {
+                item,
+                datetime: Utc::now(),
+                deployment_id: self.deployment_id,
+            })
+            .expect("sending log should succeed");
     }
 }
 
 impl log::Log for Logger {
     fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level() <= Level::Info
+        metadata.level() <= self.level
     }
 
     fn log(&self, record: &Record) {
-        if self.enabled(record.metadata()) {
-            let datetime = Utc::now();
-            let item = LogItem {
+        // Spelled out since `Layer` has an `enabled` method as well
+        if log::Log::enabled(self, record.metadata()) {
+            self.send(LogItem {
                 body: format!("{}", record.args()),
                 level: record.level(),
                 target: record.target().to_string(),
-            };
-
-            self.tx
-                .send(Log {
-                    item,
-                    datetime,
-                    deployment_id: self.deployment_id,
-                })
-                .expect("sending log should succeed");
+                fields: BTreeMap::new(),
+                spans: Vec::new(),
+            });
         }
     }
 
     fn flush(&self) {}
 }
+
+impl<S> Layer<S> for Logger
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn enabled(&self, metadata: &tracing::Metadata<'_>, _ctx: Context<'_, S>) -> bool {
+        to_log_level(metadata.level()) <= self.level
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut fields = BTreeMap::new();
+        attrs.record(&mut FieldVisitor(&mut fields));
+
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanFields(fields));
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &tracing::span::Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(SpanFields(fields)) = span.extensions_mut().get_mut::<SpanFields>() {
+                values.record(&mut FieldVisitor(fields));
+            }
+        }
+    }
+
+    fn on_event(&self, event: &tracing::Event<'_>, ctx: Context<'_, S>) {
+        let mut fields = BTreeMap::new();
+        event.record(&mut FieldVisitor(&mut fields));
+
+        let body = fields.remove("message").unwrap_or_default();
+
+        let spans = ctx
+            .event_scope(event)
+            .map(|scope| {
+                scope
+                    .from_root()
+                    .map(|span| LogSpan {
+                        name: span.name().to_string(),
+                        fields: span
+                            .extensions()
+                            .get::<SpanFields>()
+                            .map(|SpanFields(fields)| fields.clone())
+                            .unwrap_or_default(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let metadata = event.metadata();
+
+        self.send(LogItem {
+            body,
+            level: to_log_level(metadata.level()),
+            target: metadata.target().to_string(),
+            fields,
+            spans,
+        });
+    }
+}
+
+/// The fields recorded on a span so far, kept in the span's extensions.
+struct SpanFields(BTreeMap<String, String>);
+
+/// Collects the fields of a span or event as text.
+struct FieldVisitor<'a>(&'a mut BTreeMap<String, String>);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{:?}", value));
+    }
+}
+
+fn to_log_level(level: &tracing::Level) -> log::Level {
+    match *level {
+        tracing::Level::ERROR => log::Level::Error,
+        tracing::Level::WARN => log::Level::Warn,
+        tracing::Level::INFO => log::Level::Info,
+        tracing::Level::DEBUG => log::Level::Debug,
+        _ => log::Level::Trace,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use log::LevelFilter;
+    use tokio::sync::mpsc::{self, UnboundedReceiver};
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::Registry;
+    use uuid::Uuid;
+
+    use super::{Log, Logger};
+
+    fn with_layer(level: LevelFilter, f: impl FnOnce()) -> UnboundedReceiver<Log> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let subscriber = Registry::default().with(Logger::new(tx, Uuid::new_v4(), level));
+
+        tracing::subscriber::with_default(subscriber, f);
+
+        rx
+    }
+
+    fn fields(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn event_fields() {
+        let mut rx = with_layer(LevelFilter::Info, || {
+            tracing::warn!(target: "service", user = "alice", attempt = 3, "login failed");
+        });
+
+        let item = rx.try_recv().unwrap().item;
+
+        assert_eq!(item.body, "login failed");
+        assert_eq!(item.level, log::Level::Warn);
+        assert_eq!(item.target, "service");
+        assert_eq!(item.fields, fields(&[("user", "alice"), ("attempt", "3")]));
+        assert!(item.spans.is_empty());
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn event_spans() {
+        let mut rx = with_layer(LevelFilter::Info, || {
+            let request =
+                tracing::info_span!("request", path = "/hello", status = tracing::field::Empty);
+            let _request = request.enter();
+            let _handler = tracing::info_span!("handler").entered();
+
+            request.record("status", &200);
+            tracing::info!("handled");
+        });
+
+        let item = rx.try_recv().unwrap().item;
+        let spans: Vec<_> = item
+            .spans
+            .iter()
+            .map(|span| (span.name.as_str(), span.fields.clone()))
+            .collect();
+
+        assert_eq!(item.body, "handled");
+        assert!(item.fields.is_empty());
+        assert_eq!(
+            spans,
+            vec![
+                ("request", fields(&[("path", "/hello"), ("status", "200")])),
+                ("handler", BTreeMap::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn level_filter() {
+        let mut rx = with_layer(LevelFilter::Info, || {
+            tracing::debug!("hidden");
+            tracing::info!("shown");
+        });
+
+        assert_eq!(rx.try_recv().unwrap().item.body, "shown");
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,142 @@
+//! Options of the connection pools handed to services by the sqlx resources.
+
+use std::time::Duration;
+
+use anyhow::anyhow;
+use sqlx::pool::PoolOptions;
+use sqlx::Database;
+
+use crate::Error;
+
+/// How big a pool may grow and how long to wait on it for a connection, as set
+/// on the attribute of a resource like
+/// `#[shared::Postgres(max_connections = 20, acquire_timeout = "5s")]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct PoolConfig {
+    pub(crate) min_connections: u32,
+    pub(crate) max_connections: u32,
+    /// Kept as written on the attribute until the pool gets built, since the
+    /// builders have no way to report it being invalid before then
+    pub(crate) acquire_timeout: Option<String>,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            min_connections: 1,
+            max_connections: 5,
+            acquire_timeout: None,
+        }
+    }
+}
+
+impl PoolConfig {
+    /// Sets these options on the options of a pool that is yet to connect.
+    pub(crate) fn apply<DB: Database>(
+        &self,
+        options: PoolOptions<DB>,
+    ) -> Result<PoolOptions<DB>, Error> {
+        if self.max_connections == 0 {
+            return Err(Error::Custom(anyhow!(
+                "max_connections has to be at least 1"
+            )));
+        }
+        if self.min_connections > self.max_connections {
+            return Err(Error::Custom(anyhow!(
+                "min_connections ({}) cannot be more than max_connections ({})",
+                self.min_connections,
+                self.max_connections
+            )));
+        }
+
+        let mut options = options
+            .min_connections(self.min_connections)
+            .max_connections(self.max_connections);
+
+        if let Some(acquire_timeout) = &self.acquire_timeout {
+            // The connect timeout of sqlx is how long it waits for a connection from the pool
+            options = options.connect_timeout(parse_duration(acquire_timeout)?);
+        }
+
+        Ok(options)
+    }
+}
+
+/// Parses durations like `500ms`, `5s` or `2m`.
+fn parse_duration(duration: &str) -> Result<Duration, Error> {
+    let invalid = || {
+        Error::Custom(anyhow!(
+            "invalid duration '{}', expected a number followed by ms, s or m, like \"5s\"",
+            duration
+        ))
+    };
+
+    let split = duration
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let (amount, unit) = duration.split_at(split);
+    let amount: u64 = amount.parse().map_err(|_| invalid())?;
+
+    match unit {
+        "ms" => Ok(Duration::from_millis(amount)),
+        "s" => Ok(Duration::from_secs(amount)),
+        "m" => Ok(Duration::from_secs(amount * 60)),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(all(test, feature = "sqlx-postgres"))]
+mod tests {
+    use std::time::Duration;
+
+    use sqlx::postgres::PgPoolOptions;
+
+    use super::{parse_duration, PoolConfig};
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("5s").unwrap(), Duration::from_secs(5));
+        assert_eq!(parse_duration("2m").unwrap(), Duration::from_secs(120));
+
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("5").is_err());
+        assert!(parse_duration("s").is_err());
+        assert!(parse_duration("5h").is_err());
+        assert!(parse_duration("-5s").is_err());
+        assert!(parse_duration("5 s").is_err());
+    }
+
+    #[test]
+    fn apply() {
+        let config = PoolConfig {
+            min_connections: 2,
+            max_connections: 20,
+            acquire_timeout: Some("5s".to_string()),
+        };
+        assert!(config.apply(PgPoolOptions::new()).is_ok());
+        assert!(PoolConfig::default().apply(PgPoolOptions::new()).is_ok());
+    }
+
+    #[test]
+    fn apply_invalid() {
+        let config = PoolConfig {
+            max_connections: 0,
+            ..Default::default()
+        };
+        assert!(config.apply(PgPoolOptions::new()).is_err());
+
+        let config = PoolConfig {
+            min_connections: 10,
+            max_connections: 5,
+            ..Default::default()
+        };
+        assert!(config.apply(PgPoolOptions::new()).is_err());
+
+        let config = PoolConfig {
+            acquire_timeout: Some("soon".to_string()),
+            ..Default::default()
+        };
+        assert!(config.apply(PgPoolOptions::new()).is_err());
+    }
+}
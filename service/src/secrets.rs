@@ -41,10 +41,11 @@ where
     /// Read the secret with the given key from the database. Will error if a secret with the
     /// given key does not exist or otherwise could not be accessed.
     async fn get_secret(&self, key: &str) -> Result<String, Error> {
-        self.execute(sqlx::query(Self::CREATE_TABLE_QUERY)).await?;
+        self.execute(sqlx::query::<Postgres>(Self::CREATE_TABLE_QUERY))
+            .await?;
 
         let key = check_and_lower_secret_key(key)?;
-        let query = sqlx::query(Self::GET_QUERY).bind(key);
+        let query = sqlx::query::<Postgres>(Self::GET_QUERY).bind(key);
 
         self.fetch_one(query)
             .await
@@ -55,10 +56,11 @@ where
     /// Create (or overwrite if already present) a key/value secret in the database. Will error if
     /// the database could not be accessed or execution of the query otherwise failed.
     async fn set_secret(&self, key: &str, val: &str) -> Result<(), Error> {
-        self.execute(sqlx::query(Self::CREATE_TABLE_QUERY)).await?;
+        self.execute(sqlx::query::<Postgres>(Self::CREATE_TABLE_QUERY))
+            .await?;
 
         let key = check_and_lower_secret_key(key)?;
-        let query = sqlx::query(Self::SET_QUERY).bind(key).bind(val);
+        let query = sqlx::query::<Postgres>(Self::SET_QUERY).bind(key).bind(val);
 
         self.execute(query).await?;
 
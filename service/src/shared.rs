@@ -1,15 +1,52 @@
 use tokio::runtime::Runtime;
 
-use crate::{database, error::CustomError, Factory, ResourceBuilder};
+#[cfg(feature = "sqlx-postgres")]
+use crate::{database, pool::PoolConfig};
+use crate::{error::CustomError, resource, Factory, ResourceBuilder};
 use async_trait::async_trait;
 
-pub struct Postgres;
+#[cfg(feature = "sqlx-postgres")]
+pub struct Postgres {
+    name: Option<String>,
+    pool: PoolConfig,
+}
+
+#[cfg(feature = "sqlx-postgres")]
+impl Postgres {
+    /// Asks for the database with this name rather than the unnamed one
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Number of connections the pool keeps open at the least, 1 by default
+    pub fn min_connections(mut self, min_connections: u32) -> Self {
+        self.pool.min_connections = min_connections;
+        self
+    }
+
+    /// Number of connections the pool opens at the most, 5 by default
+    pub fn max_connections(mut self, max_connections: u32) -> Self {
+        self.pool.max_connections = max_connections;
+        self
+    }
+
+    /// How long to wait for a connection from the pool before giving up, like `"5s"` or `"500ms"`
+    pub fn acquire_timeout(mut self, acquire_timeout: &str) -> Self {
+        self.pool.acquire_timeout = Some(acquire_timeout.to_string());
+        self
+    }
+}
 
 /// Get an `sqlx::PgPool` from any factory
+#[cfg(feature = "sqlx-postgres")]
 #[async_trait]
 impl ResourceBuilder<sqlx::PgPool> for Postgres {
     fn new() -> Self {
-        Self {}
+        Self {
+            name: None,
+            pool: PoolConfig::default(),
+        }
     }
 
     async fn build(
@@ -18,22 +55,138 @@ impl ResourceBuilder<sqlx::PgPool> for Postgres {
         runtime: &Runtime,
     ) -> Result<sqlx::PgPool, crate::Error> {
         let connection_string = factory
-            .get_sql_connection_string(database::Type::Shared)
-            .await?;
+            .get_resource(resource::Request::new(
+                resource::Type::Database(database::Type::Shared),
+                self.name,
+            ))
+            .await?
+            .connection_string_private;
+
+        let options = self.pool.apply(sqlx::postgres::PgPoolOptions::new())?;
 
         // A sqlx Pool cannot cross runtime boundaries, so make sure to create the Pool on the service end
         let pool = runtime
+            .spawn(async move { options.connect(&connection_string).await })
+            .await
+            .map_err(CustomError::new)?
+            .map_err(CustomError::new)?;
+
+        Ok(pool)
+    }
+}
+
+#[cfg(feature = "cache-redis")]
+pub struct Redis {
+    name: Option<String>,
+}
+
+#[cfg(feature = "cache-redis")]
+impl Redis {
+    /// Asks for the user with this name rather than the unnamed one. Each user has a key prefix of
+    /// its own.
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+}
+
+/// Get a `redis::aio::ConnectionManager` from any factory
+///
+/// The instance is shared with other projects, so every key and channel has to
+/// start with the prefix in the `key_prefix` entry of the
+/// [`resource::Response::details`](crate::resource::Response::details)
+/// of the user. That is the project name and a colon, like `my-project:key`,
+/// or for a named user the project name, an underscore, the name and a colon,
+/// like `my-project_sessions:key`.
+#[cfg(feature = "cache-redis")]
+#[async_trait]
+impl ResourceBuilder<redis::aio::ConnectionManager> for Redis {
+    fn new() -> Self {
+        Self { name: None }
+    }
+
+    async fn build(
+        self,
+        factory: &mut dyn Factory,
+        runtime: &Runtime,
+    ) -> Result<redis::aio::ConnectionManager, crate::Error> {
+        let connection_string = factory
+            .get_resource(resource::Request::new(resource::Type::Redis, self.name))
+            .await?
+            .connection_string_private;
+
+        // The manager reconnects from a task of its own, so make sure to create it on the service end
+        let manager = runtime
             .spawn(async move {
-                sqlx::postgres::PgPoolOptions::new()
-                    .min_connections(1)
-                    .max_connections(5)
-                    .connect(&connection_string)
-                    .await
+                let client = redis::Client::open(connection_string)?;
+
+                redis::aio::ConnectionManager::new(client).await
             })
             .await
             .map_err(CustomError::new)?
             .map_err(CustomError::new)?;
 
-        Ok(pool)
+        Ok(manager)
+    }
+}
+
+#[cfg(feature = "mongodb-integration")]
+pub struct MongoDb {
+    name: Option<String>,
+}
+
+#[cfg(feature = "mongodb-integration")]
+impl MongoDb {
+    /// Asks for the database with this name rather than the unnamed one
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+}
+
+/// Get a `mongodb::Database` from any factory
+#[cfg(feature = "mongodb-integration")]
+#[async_trait]
+impl ResourceBuilder<mongodb::Database> for MongoDb {
+    fn new() -> Self {
+        Self { name: None }
+    }
+
+    async fn build(
+        self,
+        factory: &mut dyn Factory,
+        runtime: &Runtime,
+    ) -> Result<mongodb::Database, crate::Error> {
+        let connection_string = factory
+            .get_resource(resource::Request::new(resource::Type::MongoDb, self.name))
+            .await?
+            .connection_string_private;
+
+        // The client monitors the servers from tasks of its own and parsing may already resolve them, so make sure
+        // to do both on the service end
+        let database = runtime
+            .spawn(async move {
+                let client_options = mongodb::options::ClientOptions::parse(connection_string)
+                    .await
+                    .map_err(CustomError::new)?;
+                let database_name = client_options.default_database.clone().ok_or_else(|| {
+                    crate::Error::Database("connection string does not name a database".to_string())
+                })?;
+                let database = mongodb::Client::with_options(client_options)
+                    .map_err(CustomError::new)?
+                    .database(&database_name);
+
+                // Fail now rather than on the first query when the database cannot be reached
+                database
+                    .run_command(mongodb::bson::doc! { "ping": 1 }, None)
+                    .await
+                    .map_err(CustomError::new)?;
+
+                Ok::<_, crate::Error>(database)
+            })
+            .await
+            .map_err(CustomError::new)??;
+
+        Ok(database)
     }
 }
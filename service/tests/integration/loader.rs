@@ -1,14 +1,17 @@
 use crate::helpers::{loader::build_so_create_loader, sqlx::PostgresInstance};
 
+use shuttle_service::error::CustomError;
 use shuttle_service::loader::LoaderError;
-use shuttle_service::{database, Error, Factory};
+use shuttle_service::log::LevelFilter;
+use shuttle_service::{resource, Error, Factory};
 
+use std::future;
 use std::net::{Ipv4Addr, SocketAddr};
 use std::process::exit;
 use std::time::Duration;
 
 use async_trait::async_trait;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
 use uuid::Uuid;
 
 const RESOURCES_PATH: &str = "tests/resources";
@@ -27,19 +30,38 @@ impl DummyFactory {
 
 #[async_trait]
 impl Factory for DummyFactory {
-    async fn get_sql_connection_string(&mut self, _: database::Type) -> Result<String, Error> {
-        let uri = if let Some(postgres_instance) = &self.postgres_instance {
-            postgres_instance.get_uri()
-        } else {
-            let postgres_instance = PostgresInstance::new();
-            postgres_instance.wait_for_ready();
-            postgres_instance.wait_for_connectable().await;
-            let uri = postgres_instance.get_uri();
-            self.postgres_instance = Some(postgres_instance);
-            uri
+    async fn get_resource(
+        &mut self,
+        request: resource::Request,
+    ) -> Result<resource::Response, Error> {
+        let uri = match request.r#type {
+            resource::Type::Database(_) => {
+                if let Some(postgres_instance) = &self.postgres_instance {
+                    postgres_instance.get_uri()
+                } else {
+                    let postgres_instance = PostgresInstance::new();
+                    postgres_instance.wait_for_ready();
+                    postgres_instance.wait_for_connectable().await;
+                    let uri = postgres_instance.get_uri();
+                    self.postgres_instance = Some(postgres_instance);
+                    uri
+                }
+            }
+            _ => {
+                return Err(Error::Custom(CustomError::msg(format!(
+                    "the dummy factory cannot provision a {}",
+                    request
+                ))))
+            }
         };
 
-        Ok(uri)
+        Ok(resource::Response {
+            r#type: request.r#type,
+            name: request.name,
+            connection_string_private: uri.clone(),
+            connection_string_public: uri,
+            details: Default::default(),
+        })
     }
 }
 
@@ -57,8 +79,15 @@ async fn sleep_async() {
     let addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 8001);
     let deployment_id = Uuid::new_v4();
     let (tx, _rx) = mpsc::unbounded_channel();
-    let (handler, _) = loader
-        .load(&mut factory, addr, tx, deployment_id)
+    let (handler, _, _) = loader
+        .load(
+            &mut factory,
+            addr,
+            future::pending(),
+            tx,
+            deployment_id,
+            LevelFilter::Info,
+        )
         .await
         .unwrap();
 
@@ -84,8 +113,15 @@ async fn sleep() {
     let addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 8001);
     let deployment_id = Uuid::new_v4();
     let (tx, _rx) = mpsc::unbounded_channel();
-    let (handler, _) = loader
-        .load(&mut factory, addr, tx, deployment_id)
+    let (handler, _, _) = loader
+        .load(
+            &mut factory,
+            addr,
+            future::pending(),
+            tx,
+            deployment_id,
+            LevelFilter::Info,
+        )
         .await
         .unwrap();
 
@@ -116,8 +152,15 @@ async fn sqlx_pool() {
     let addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 8001);
     let deployment_id = Uuid::new_v4();
     let (tx, mut rx) = mpsc::unbounded_channel();
-    let (handler, _) = loader
-        .load(&mut factory, addr, tx, deployment_id)
+    let (handler, _, _) = loader
+        .load(
+            &mut factory,
+            addr,
+            future::pending(),
+            tx,
+            deployment_id,
+            LevelFilter::Info,
+        )
         .await
         .unwrap();
 
@@ -143,7 +186,17 @@ async fn build_panic() {
     let deployment_id = Uuid::new_v4();
     let (tx, _) = mpsc::unbounded_channel();
 
-    if let Err(Error::BuildPanic(msg)) = loader.load(&mut factory, addr, tx, deployment_id).await {
+    if let Err(Error::BuildPanic(msg)) = loader
+        .load(
+            &mut factory,
+            addr,
+            future::pending(),
+            tx,
+            deployment_id,
+            LevelFilter::Info,
+        )
+        .await
+    {
         assert_eq!(&msg, "panic in build");
     } else {
         panic!("expected `Err(Error::BuildPanic(_))`");
@@ -159,8 +212,15 @@ async fn bind_panic() {
     let deployment_id = Uuid::new_v4();
     let (tx, _) = mpsc::unbounded_channel();
 
-    let (handle, _) = loader
-        .load(&mut factory, addr, tx, deployment_id)
+    let (handle, _, _) = loader
+        .load(
+            &mut factory,
+            addr,
+            future::pending(),
+            tx,
+            deployment_id,
+            LevelFilter::Info,
+        )
         .await
         .unwrap();
 
@@ -170,3 +230,64 @@ async fn bind_panic() {
         panic!("expected `Err(Error::BindPanic(_))`");
     }
 }
+
+#[tokio::test]
+async fn worker() {
+    let loader = build_so_create_loader(RESOURCES_PATH, "worker").unwrap();
+
+    let mut factory = DummyFactory::new();
+    let addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 8001);
+    let deployment_id = Uuid::new_v4();
+    let (tx, _) = mpsc::unbounded_channel();
+
+    let (handle, _, routable) = loader
+        .load(
+            &mut factory,
+            addr,
+            future::pending(),
+            tx,
+            deployment_id,
+            LevelFilter::Info,
+        )
+        .await
+        .unwrap();
+
+    assert!(!routable);
+    handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn shutdown() {
+    let loader = build_so_create_loader(RESOURCES_PATH, "sleep-async").unwrap();
+
+    let mut factory = DummyFactory::new();
+    let addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 8001);
+    let deployment_id = Uuid::new_v4();
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let (handle, _, _) = loader
+        .load(
+            &mut factory,
+            addr,
+            async move {
+                let _ = shutdown_rx.await;
+            },
+            tx,
+            deployment_id,
+            LevelFilter::Info,
+        )
+        .await
+        .unwrap();
+
+    // Give service some time to start up
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    shutdown_tx.send(()).unwrap();
+
+    // Time is less than sleep in service
+    tokio::time::timeout(Duration::from_secs(5), handle)
+        .await
+        .expect("service should stop once it is shut down")
+        .unwrap()
+        .unwrap();
+}
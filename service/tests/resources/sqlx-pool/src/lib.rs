@@ -7,7 +7,9 @@ struct PoolService {
 }
 
 #[shuttle_service::main]
-async fn init(#[shared::Postgres] pool: PgPool) -> Result<PoolService, shuttle_service::Error> {
+async fn init(
+    #[shared::Postgres(max_connections = 10, acquire_timeout = "5s")] pool: PgPool,
+) -> Result<PoolService, shuttle_service::Error> {
     Ok(PoolService { pool })
 }
 
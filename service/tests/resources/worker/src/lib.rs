@@ -0,0 +1,6 @@
+use shuttle_service::{ShuttleWorker, WorkerService};
+
+#[shuttle_service::main]
+async fn worker() -> ShuttleWorker {
+    Ok(WorkerService::new(|| async { Ok(()) }))
+}
@@ -145,9 +145,11 @@ locals {
   shuttle_provisioner_content = templatefile(
     "${path.module}/systemd/system/shuttle-provisioner.service.tftpl",
     {
-      docker_image = local.docker_provisioner_image,
-      fqdn         = var.pg_fqdn,
-      pg_password  = var.postgres_password,
+      docker_image     = local.docker_provisioner_image,
+      fqdn             = var.pg_fqdn,
+      pg_password      = var.postgres_password,
+      redis_password   = var.redis_password,
+      mongodb_password = var.mongodb_password,
     }
   )
   shuttle_db_content = templatefile(
@@ -157,6 +159,20 @@ locals {
       pg_password = var.postgres_password,
     }
   )
+  shuttle_redis_content = templatefile(
+    "${path.module}/systemd/system/shuttle-redis.service.tftpl",
+    {
+      data_dir       = local.data_dir,
+      redis_password = var.redis_password,
+    }
+  )
+  shuttle_mongodb_content = templatefile(
+    "${path.module}/systemd/system/shuttle-mongodb.service.tftpl",
+    {
+      data_dir         = local.data_dir,
+      mongodb_password = var.mongodb_password,
+    }
+  )
 }
 
 data "cloudinit_config" "backend" {
@@ -172,6 +188,8 @@ data "cloudinit_config" "backend" {
         shuttle_backend_content     = base64encode(local.shuttle_backend_content)
         shuttle_provisioner_content = base64encode(local.shuttle_provisioner_content)
         shuttle_db_content          = base64encode(local.shuttle_db_content)
+        shuttle_redis_content       = base64encode(local.shuttle_redis_content)
+        shuttle_mongodb_content     = base64encode(local.shuttle_mongodb_content)
       }
     )
     filename = "cloud-config.yaml"
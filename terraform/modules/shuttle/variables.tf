@@ -31,6 +31,16 @@ variable "postgres_password" {
   description = "Root password for postgres instance"
 }
 
+variable "redis_password" {
+  type        = string
+  description = "Password of the default user of the redis instance"
+}
+
+variable "mongodb_password" {
+  type        = string
+  description = "Root password for mongodb instance"
+}
+
 variable "proxy_container_port" {
   type        = number
   description = "Port reverse proxy will be reachable at"
@@ -3,6 +3,16 @@ variable "postgres_password" {
   description = "Root password for postgres instance"
 }
 
+variable "redis_password" {
+  type        = string
+  description = "Password of the default user of the redis instance"
+}
+
+variable "mongodb_password" {
+  type        = string
+  description = "Root password for mongodb instance"
+}
+
 variable "shuttle_admin_secret" {
   type        = string
   description = "Secret for the shuttle admin user"